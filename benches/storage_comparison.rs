@@ -1,6 +1,4 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use reqwest;
-use serde_json::json;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 
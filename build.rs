@@ -0,0 +1,4 @@
+// Re-embed `sqlx::migrate!` sets when a migration file changes
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -16,8 +16,8 @@
     package = pkgs.mariadb;
     initialDatabases = [
       { 
+        # Schema is applied by the application: cargo run -- --migrate
         name = "structure_comparison";
-        schema = ./migrations/001_init.sql;
       }
     ];
     ensureUsers = [
@@ -1,5 +1,4 @@
 -- Column type storage (normalized table structure)
-DROP TABLE IF EXISTS users_column;
 CREATE TABLE users_column (
     id CHAR(36) PRIMARY KEY,
     name VARCHAR(255) NOT NULL,
@@ -13,7 +12,6 @@ CREATE TABLE users_column (
 );
 
 -- JSON type storage (denormalized table structure)
-DROP TABLE IF EXISTS users_json;
 CREATE TABLE users_json (
     id CHAR(36) PRIMARY KEY,
     data JSON NOT NULL,
@@ -0,0 +1,14 @@
+-- Hybrid storage: JSON document plus indexed generated columns.
+-- MariaDB cannot index JSON_EXTRACT expressions directly, but it can index
+-- a VIRTUAL column computed from them.
+CREATE TABLE users_hybrid (
+    id CHAR(36) PRIMARY KEY,
+    data JSON NOT NULL,
+    email VARCHAR(255) AS (JSON_UNQUOTE(JSON_EXTRACT(data, '$.email'))) VIRTUAL,
+    age INTEGER AS (CAST(JSON_EXTRACT(data, '$.age') AS SIGNED)) VIRTUAL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_users_hybrid_created_at ON users_hybrid(created_at);
+CREATE INDEX idx_users_hybrid_email ON users_hybrid(email);
+CREATE INDEX idx_users_hybrid_age ON users_hybrid(age);
@@ -0,0 +1,22 @@
+-- The users_json document encoded as MessagePack, CBOR and bincode BLOBs
+CREATE TABLE users_msgpack (
+    id CHAR(36) PRIMARY KEY,
+    data LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE users_cbor (
+    id CHAR(36) PRIMARY KEY,
+    data LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE users_bincode (
+    id CHAR(36) PRIMARY KEY,
+    data LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_users_msgpack_created_at ON users_msgpack(created_at);
+CREATE INDEX idx_users_cbor_created_at ON users_cbor(created_at);
+CREATE INDEX idx_users_bincode_created_at ON users_bincode(created_at);
@@ -0,0 +1,15 @@
+-- The users_json document compressed in the application with zstd and LZ4
+CREATE TABLE users_json_zstd (
+    id CHAR(36) PRIMARY KEY,
+    data LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE users_json_lz4 (
+    id CHAR(36) PRIMARY KEY,
+    data LONGBLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_users_json_zstd_created_at ON users_json_zstd(created_at);
+CREATE INDEX idx_users_json_lz4_created_at ON users_json_lz4(created_at);
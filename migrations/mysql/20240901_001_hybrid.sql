@@ -0,0 +1,12 @@
+-- Hybrid storage: JSON document plus indexed generated columns
+CREATE TABLE users_hybrid (
+    id CHAR(36) PRIMARY KEY,
+    data JSON NOT NULL,
+    email VARCHAR(255) GENERATED ALWAYS AS (JSON_UNQUOTE(JSON_EXTRACT(data, '$.email'))) VIRTUAL,
+    age INTEGER GENERATED ALWAYS AS (CAST(JSON_EXTRACT(data, '$.age') AS SIGNED)) VIRTUAL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_users_hybrid_created_at ON users_hybrid(created_at);
+CREATE INDEX idx_users_hybrid_email ON users_hybrid(email);
+CREATE INDEX idx_users_hybrid_age ON users_hybrid(age);
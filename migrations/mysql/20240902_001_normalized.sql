@@ -0,0 +1,31 @@
+-- Fully normalized storage: scalar fields in users_normalized, the profile
+-- map and list in child tables (`key` is reserved in MySQL, hence pref_key)
+CREATE TABLE users_normalized (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url VARCHAR(500),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE user_preferences (
+    user_id CHAR(36) NOT NULL,
+    pref_key VARCHAR(100) NOT NULL,
+    pref_value VARCHAR(255) NOT NULL,
+    PRIMARY KEY (user_id, pref_key),
+    FOREIGN KEY (user_id) REFERENCES users_normalized(id) ON DELETE CASCADE
+);
+
+CREATE TABLE user_social_links (
+    user_id CHAR(36) NOT NULL,
+    position INTEGER NOT NULL,
+    url VARCHAR(500) NOT NULL,
+    PRIMARY KEY (user_id, position),
+    FOREIGN KEY (user_id) REFERENCES users_normalized(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_users_normalized_created_at ON users_normalized(created_at);
+CREATE INDEX idx_users_normalized_email ON users_normalized(email);
+CREATE INDEX idx_users_normalized_age ON users_normalized(age);
@@ -0,0 +1,45 @@
+-- Complex profiles in columns: statistics and metadata as columns,
+-- achievements and tags in child tables, preferences and social links as JSON
+CREATE TABLE complex_column (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url VARCHAR(500),
+    preferences JSON,
+    social_links JSON,
+    posts_count BIGINT NOT NULL,
+    followers_count BIGINT NOT NULL,
+    following_count BIGINT NOT NULL,
+    likes_received BIGINT NOT NULL,
+    comments_made BIGINT NOT NULL,
+    metadata_created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_login TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    login_count BIGINT NOT NULL,
+    is_verified BOOLEAN NOT NULL,
+    is_premium BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE complex_achievements (
+    user_id CHAR(36) NOT NULL,
+    position INTEGER NOT NULL,
+    achievement_id VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    earned_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    points BIGINT NOT NULL,
+    PRIMARY KEY (user_id, position),
+    FOREIGN KEY (user_id) REFERENCES complex_column(id) ON DELETE CASCADE
+);
+
+CREATE TABLE complex_tags (
+    user_id CHAR(36) NOT NULL,
+    position INTEGER NOT NULL,
+    tag VARCHAR(100) NOT NULL,
+    PRIMARY KEY (user_id, position),
+    FOREIGN KEY (user_id) REFERENCES complex_column(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_complex_column_created_at ON complex_column(created_at);
@@ -0,0 +1,13 @@
+-- The email index of users_json cast the quoted JSON string with the
+-- collation of the session that created it, which lookups from a session
+-- with another collation could not use. Spell out charset and collation, and
+-- index the unquoted address
+DROP INDEX idx_users_json_email ON users_json;
+CREATE INDEX idx_users_json_email ON users_json((CAST(JSON_UNQUOTE(JSON_EXTRACT(data, '$.email')) AS CHAR(255) CHARACTER SET utf8mb4) COLLATE utf8mb4_bin));
+
+-- The email columns of the other layouts took the case-insensitive table
+-- default; compare emails with the same binary collation in every layout
+ALTER TABLE users_column MODIFY email VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL;
+ALTER TABLE users_normalized MODIFY email VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL;
+ALTER TABLE users_hybrid MODIFY email VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin
+    GENERATED ALWAYS AS (JSON_UNQUOTE(JSON_EXTRACT(data, '$.email'))) VIRTUAL;
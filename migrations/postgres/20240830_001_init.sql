@@ -0,0 +1,49 @@
+-- Column type storage (normalized table structure)
+CREATE TABLE users_column (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url VARCHAR(500),
+    preferences JSONB,
+    social_links JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- JSON type storage (text JSON, reparsed on every access)
+CREATE TABLE users_json (
+    id CHAR(36) PRIMARY KEY,
+    data JSON NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- JSONB type storage (decomposed binary JSON)
+CREATE TABLE users_jsonb (
+    id CHAR(36) PRIMARY KEY,
+    data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- JSONB type storage with a GIN index over the whole document
+CREATE TABLE users_jsonb_gin (
+    id CHAR(36) PRIMARY KEY,
+    data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Create indexes
+CREATE INDEX idx_users_column_created_at ON users_column(created_at);
+CREATE INDEX idx_users_column_email ON users_column(email);
+CREATE INDEX idx_users_column_age ON users_column(age);
+
+CREATE INDEX idx_users_json_created_at ON users_json(created_at);
+CREATE INDEX idx_users_json_email ON users_json((data->>'email'));
+CREATE INDEX idx_users_json_age ON users_json(((data->>'age')::INTEGER));
+
+CREATE INDEX idx_users_jsonb_created_at ON users_jsonb(created_at);
+CREATE INDEX idx_users_jsonb_email ON users_jsonb((data->>'email'));
+CREATE INDEX idx_users_jsonb_age ON users_jsonb(((data->>'age')::INTEGER));
+
+CREATE INDEX idx_users_jsonb_gin_created_at ON users_jsonb_gin(created_at);
+CREATE INDEX idx_users_jsonb_gin_data ON users_jsonb_gin USING GIN (data jsonb_path_ops);
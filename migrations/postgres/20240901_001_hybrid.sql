@@ -0,0 +1,13 @@
+-- Hybrid storage: JSONB document plus indexed generated columns.
+-- PostgreSQL only supports STORED generated columns.
+CREATE TABLE users_hybrid (
+    id CHAR(36) PRIMARY KEY,
+    data JSONB NOT NULL,
+    email VARCHAR(255) GENERATED ALWAYS AS (data->>'email') STORED,
+    age INTEGER GENERATED ALWAYS AS ((data->>'age')::INTEGER) STORED,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_users_hybrid_created_at ON users_hybrid(created_at);
+CREATE INDEX idx_users_hybrid_email ON users_hybrid(email);
+CREATE INDEX idx_users_hybrid_age ON users_hybrid(age);
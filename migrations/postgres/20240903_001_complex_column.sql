@@ -0,0 +1,43 @@
+-- Complex profiles in columns: statistics and metadata as columns,
+-- achievements and tags in child tables, preferences and social links as JSONB
+CREATE TABLE complex_column (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url VARCHAR(500),
+    preferences JSONB,
+    social_links JSONB,
+    posts_count BIGINT NOT NULL,
+    followers_count BIGINT NOT NULL,
+    following_count BIGINT NOT NULL,
+    likes_received BIGINT NOT NULL,
+    comments_made BIGINT NOT NULL,
+    metadata_created_at TIMESTAMPTZ NOT NULL,
+    last_login TIMESTAMPTZ NOT NULL,
+    login_count BIGINT NOT NULL,
+    is_verified BOOLEAN NOT NULL,
+    is_premium BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE complex_achievements (
+    user_id CHAR(36) NOT NULL REFERENCES complex_column(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    achievement_id VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    earned_at TIMESTAMPTZ NOT NULL,
+    points BIGINT NOT NULL,
+    PRIMARY KEY (user_id, position)
+);
+
+CREATE TABLE complex_tags (
+    user_id CHAR(36) NOT NULL REFERENCES complex_column(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tag VARCHAR(100) NOT NULL,
+    PRIMARY KEY (user_id, position)
+);
+
+CREATE INDEX idx_complex_column_created_at ON complex_column(created_at);
@@ -8,14 +8,14 @@ CREATE TABLE users_column (
     avatar_url TEXT,
     preferences TEXT, -- JSON as TEXT in SQLite
     social_links TEXT, -- JSON as TEXT in SQLite
-    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
 );
 
 -- JSON type storage (denormalized table structure)
 CREATE TABLE users_json (
     id TEXT PRIMARY KEY,
     data TEXT NOT NULL, -- JSON as TEXT in SQLite
-    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
 );
 
 -- Create indexes
@@ -0,0 +1,12 @@
+-- Hybrid storage: JSON document plus indexed generated columns
+CREATE TABLE users_hybrid (
+    id TEXT PRIMARY KEY,
+    data TEXT NOT NULL, -- JSON as TEXT in SQLite
+    email TEXT GENERATED ALWAYS AS (json_extract(data, '$.email')) VIRTUAL,
+    age INTEGER GENERATED ALWAYS AS (json_extract(data, '$.age')) VIRTUAL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE INDEX idx_users_hybrid_created_at ON users_hybrid(created_at);
+CREATE INDEX idx_users_hybrid_email ON users_hybrid(email);
+CREATE INDEX idx_users_hybrid_age ON users_hybrid(age);
@@ -0,0 +1,29 @@
+-- Fully normalized storage: scalar fields in users_normalized, the profile
+-- map and list in child tables
+CREATE TABLE users_normalized (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE TABLE user_preferences (
+    user_id TEXT NOT NULL REFERENCES users_normalized(id) ON DELETE CASCADE,
+    pref_key TEXT NOT NULL,
+    pref_value TEXT NOT NULL,
+    PRIMARY KEY (user_id, pref_key)
+);
+
+CREATE TABLE user_social_links (
+    user_id TEXT NOT NULL REFERENCES users_normalized(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (user_id, position)
+);
+
+CREATE INDEX idx_users_normalized_created_at ON users_normalized(created_at);
+CREATE INDEX idx_users_normalized_email ON users_normalized(email);
+CREATE INDEX idx_users_normalized_age ON users_normalized(age);
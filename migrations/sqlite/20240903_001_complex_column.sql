@@ -0,0 +1,43 @@
+-- Complex profiles in columns: statistics and metadata as columns,
+-- achievements and tags in child tables, preferences and social links as JSON
+CREATE TABLE complex_column (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    bio TEXT,
+    avatar_url TEXT,
+    preferences TEXT,
+    social_links TEXT,
+    posts_count INTEGER NOT NULL,
+    followers_count INTEGER NOT NULL,
+    following_count INTEGER NOT NULL,
+    likes_received INTEGER NOT NULL,
+    comments_made INTEGER NOT NULL,
+    metadata_created_at TEXT NOT NULL,
+    last_login TEXT NOT NULL,
+    login_count INTEGER NOT NULL,
+    is_verified BOOLEAN NOT NULL,
+    is_premium BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE TABLE complex_achievements (
+    user_id TEXT NOT NULL REFERENCES complex_column(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    achievement_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    earned_at TEXT NOT NULL,
+    points INTEGER NOT NULL,
+    PRIMARY KEY (user_id, position)
+);
+
+CREATE TABLE complex_tags (
+    user_id TEXT NOT NULL REFERENCES complex_column(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (user_id, position)
+);
+
+CREATE INDEX idx_complex_column_created_at ON complex_column(created_at);
@@ -0,0 +1,22 @@
+-- The users_json document encoded as MessagePack, CBOR and bincode BLOBs
+CREATE TABLE users_msgpack (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE TABLE users_cbor (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE TABLE users_bincode (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE INDEX idx_users_msgpack_created_at ON users_msgpack(created_at);
+CREATE INDEX idx_users_cbor_created_at ON users_cbor(created_at);
+CREATE INDEX idx_users_bincode_created_at ON users_bincode(created_at);
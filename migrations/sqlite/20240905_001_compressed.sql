@@ -0,0 +1,15 @@
+-- The users_json document compressed in the application with zstd and LZ4
+CREATE TABLE users_json_zstd (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE TABLE users_json_lz4 (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000000Z', 'now'))
+);
+
+CREATE INDEX idx_users_json_zstd_created_at ON users_json_zstd(created_at);
+CREATE INDEX idx_users_json_lz4_created_at ON users_json_lz4(created_at);
@@ -17,7 +17,7 @@ devenv shell -c "mysql -u root -e 'CREATE DATABASE IF NOT EXISTS structure_compa
 
 # Application startup
 echo "🖥️  Starting application..."
-devenv shell -c "cargo run" &
+devenv shell -c "cargo run -- --migrate" &
 APP_PID=$!
 
 # Wait for app to start
@@ -29,6 +29,11 @@ echo "📊 Running benchmarks..."
 echo "🔧 Generating test data..."
 curl -X POST http://localhost:3000/generate/column/10000
 curl -X POST http://localhost:3000/generate/json/10000
+curl -X POST http://localhost:3000/generate/hybrid/10000
+curl -X POST http://localhost:3000/generate/normalized/10000
+for format in msgpack cbor bincode; do
+    curl -X POST "http://localhost:3000/generate/${format}/10000"
+done
 
 echo "⚡ Running performance tests..."
 
@@ -44,6 +49,37 @@ for i in {1..5}; do
     curl -s http://localhost:3000/benchmark/json/1000 | jq '.duration_ms'
 done
 
+# Hybrid storage benchmark
+echo "📈 Hybrid storage benchmark..."
+for i in {1..5}; do
+    curl -s http://localhost:3000/benchmark/hybrid/1000 | jq '.duration_ms'
+done
+
+# Normalized storage benchmark, JOIN and two-query reads
+echo "📈 Normalized storage benchmark..."
+for strategy in join two_query; do
+    for i in {1..5}; do
+        curl -s "http://localhost:3000/benchmark/normalized/1000?strategy=${strategy}" | jq '.duration_ms'
+    done
+done
+
+# Binary document benchmarks: decode time and stored bytes per user
+echo "📈 MessagePack, CBOR and bincode storage benchmark..."
+for format in msgpack cbor bincode; do
+    for i in {1..5}; do
+        curl -s "http://localhost:3000/benchmark/${format}/1000" | jq -c '{duration_ms, parse_us: .phases.parse_us, bytes_per_user: .stored.bytes_per_user}'
+    done
+done
+
+# Complex documents, plain and compressed: decompress time and stored bytes per user
+echo "📈 Compressed complex document benchmark..."
+for compression in none zstd lz4; do
+    curl -s -X POST "http://localhost:3000/generate/complex/10000?compression=${compression}" > /dev/null
+    for i in {1..5}; do
+        curl -s "http://localhost:3000/benchmark/complex/1000?compression=${compression}" | jq -c '{duration_ms, decompress_us: .phases.decompress_us, bytes_per_user: .stored.bytes_per_user}'
+    done
+done
+
 # Cleanup
 kill $APP_PID $DEVENV_PID
 
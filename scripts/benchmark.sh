@@ -5,7 +5,7 @@ echo "🚀 Starting performance comparison..."
 # Server startup verification
 echo "Checking if server is running..."
 if ! curl -s http://localhost:3000/users/column > /dev/null; then
-    echo "❌ Server is not running. Please start with: cargo run"
+    echo "❌ Server is not running. Please start with: cargo run -- --migrate"
     exit 1
 fi
 
@@ -0,0 +1,45 @@
+#!/bin/bash
+
+# Runs the column vs JSON vs JSONB comparison against a throwaway PostgreSQL
+# cluster started from the local `postgres` binaries (initdb/pg_ctl).
+
+PGPORT=${PGPORT:-5433}
+PGDATA_DIR=$(mktemp -d)
+export DATABASE_URL="postgres://postgres@127.0.0.1:${PGPORT}/structure_comparison"
+
+echo "🐘 Initializing PostgreSQL cluster in ${PGDATA_DIR}..."
+initdb -D "$PGDATA_DIR" -U postgres --auth=trust > /dev/null || exit 1
+pg_ctl -D "$PGDATA_DIR" -o "-p ${PGPORT} -k ${PGDATA_DIR}" -l "${PGDATA_DIR}/server.log" -w start || exit 1
+createdb -h 127.0.0.1 -p "$PGPORT" -U postgres structure_comparison
+
+cleanup() {
+    kill $APP_PID 2>/dev/null
+    pg_ctl -D "$PGDATA_DIR" -m fast stop > /dev/null
+    rm -rf "$PGDATA_DIR"
+}
+trap cleanup EXIT
+
+# Application startup
+echo "🖥️  Starting application..."
+cargo run -- --migrate &
+APP_PID=$!
+
+# Wait for app to start
+until curl -s http://localhost:3000/users/column > /dev/null; do
+    sleep 1
+done
+
+echo "🔧 Generating test data..."
+for layout in column json jsonb jsonb_gin hybrid normalized msgpack cbor bincode json_zstd json_lz4; do
+    curl -s -X POST "http://localhost:3000/generate/${layout}/10000" | jq -c
+done
+
+echo "⚡ Running performance tests..."
+for layout in column json jsonb jsonb_gin hybrid normalized msgpack cbor bincode json_zstd json_lz4; do
+    echo "📈 ${layout} storage benchmark..."
+    for i in {1..5}; do
+        curl -s "http://localhost:3000/benchmark/${layout}/1000" | jq '.duration_ms'
+    done
+done
+
+echo "✅ Benchmark complete!"
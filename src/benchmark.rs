@@ -0,0 +1,1114 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::cache::{self, CacheMode, Eviction};
+use crate::data_generator;
+use crate::db::Db;
+use crate::explain::{self, ExplainMode, QueryPlan};
+use crate::memory::{MemoryStats, RunStart};
+use crate::models::{BioAnalysis, ComplexUser, TagAnalysis, User};
+use crate::stats::{CounterDeltas, CounterWindow};
+use crate::store::{
+    ComplexAggregate, ComplexColumnStore, DocumentStore, FetchMode, FetchPhases, JsonStore, UserFilter, UserStore,
+    WriteMode,
+};
+
+/// Coefficient of variation (stddev / mean) above which a run is flagged as
+/// too noisy to compare.
+const HIGH_VARIANCE_CV: f64 = 0.10;
+
+/// One benchmark, as returned by `/benchmark/*` and saved by the `bench`
+/// command. `duration_ms` is the median of the measured iterations.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub storage_type: String,
+    pub count: i32,
+    pub duration_ms: u128,
+    pub records_processed: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing_details: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<PhaseBreakdown>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored: Option<StoredSize>,
+    /// Change of the status counters over the measured runs, on
+    /// MySQL/MariaDB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counters: Option<CounterDeltas>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plans: Option<Vec<QueryPlan>>,
+}
+
+/// Size of the whole table a read benchmark ran against, from
+/// `UserStore::stored_bytes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSize {
+    pub users: i64,
+    pub bytes: i64,
+    pub bytes_per_user: f64,
+}
+
+impl StoredSize {
+    async fn of<S: UserStore + ?Sized>(store: &S) -> anyhow::Result<Self> {
+        let users = store.count().await?;
+        let bytes = store.stored_bytes().await?;
+        Ok(Self {
+            users,
+            bytes,
+            bytes_per_user: if users > 0 { bytes as f64 / users as f64 } else { 0.0 },
+        })
+    }
+}
+
+/// How often a benchmark runs: `warmup` discarded runs, then `iterations`
+/// measured ones, each after evicting the cache with `cache=cold`. With
+/// `explain`, the first warmup run captures the query plans; one is added
+/// if `warmup` is 0.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct RunOptions {
+    #[serde(default = "RunOptions::default_iterations")]
+    pub iterations: u32,
+    #[serde(default)]
+    pub warmup: u32,
+    #[serde(default)]
+    pub explain: ExplainMode,
+    #[serde(default)]
+    pub cache: CacheMode,
+}
+
+impl RunOptions {
+    fn default_iterations() -> u32 {
+        1
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            iterations: Self::default_iterations(),
+            warmup: 0,
+            explain: ExplainMode::Off,
+            cache: CacheMode::Warm,
+        }
+    }
+}
+
+/// Distribution of the measured iterations, in microseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingStats {
+    pub iterations: u32,
+    pub warmup: u32,
+    /// What the measured runs got: `cold` only when `cache=cold` evicted
+    /// the cache before every one of them.
+    #[serde(default)]
+    pub cache: CacheMode,
+    /// With `cache=cold`, the first eviction that left pages cached, or
+    /// `evicted` if none did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eviction: Option<Eviction>,
+    pub min_us: u64,
+    pub max_us: u64,
+    pub mean_us: f64,
+    pub stddev_us: f64,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub high_variance: bool,
+}
+
+impl TimingStats {
+    fn new(options: &RunOptions, durations: &[Duration], eviction: Option<Eviction>) -> Self {
+        let mut samples: Vec<u64> = durations.iter().map(|d| d.as_micros() as u64).collect();
+        samples.sort_unstable();
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<u64>() as f64 / n;
+        let variance = if samples.len() > 1 {
+            samples.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        let stddev = variance.sqrt();
+
+        Self {
+            iterations: options.iterations,
+            warmup: options.warmup,
+            cache: if eviction.as_ref().is_some_and(Eviction::is_complete) {
+                CacheMode::Cold
+            } else {
+                CacheMode::Warm
+            },
+            eviction,
+            min_us: samples[0],
+            max_us: samples[samples.len() - 1],
+            mean_us: mean,
+            stddev_us: stddev,
+            p50_us: percentile(&samples, 50.0),
+            p95_us: percentile(&samples, 95.0),
+            p99_us: percentile(&samples, 99.0),
+            high_variance: mean > 0.0 && stddev / mean > HIGH_VARIANCE_CV,
+        }
+    }
+}
+
+/// Mean time per phase of the measured iterations, in microseconds.
+///
+/// `query` runs until the first row arrives (server-side execution),
+/// `transfer` receives the remaining rows, `decode` is `Row::get`,
+/// `decompress` undoes application-side compression and `parse` is the JSON
+/// parser on the decoded text, or the binary decoder on a BLOB. `process` is the application-side
+/// work after parsing, where a benchmark has any.
+///
+/// `first_parsed` is not a phase but the time from the start of the fetch
+/// until the first row was parsed, i.e. the time to first row as the
+/// application sees it. Buffered fetches only get there after `transfer`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseBreakdown {
+    pub query_us: f64,
+    pub transfer_us: f64,
+    pub decode_us: f64,
+    #[serde(default)]
+    pub decompress_us: f64,
+    pub parse_us: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_us: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_parsed_us: Option<f64>,
+}
+
+impl PhaseBreakdown {
+    fn new(samples: &[(FetchPhases, Option<Duration>)]) -> Self {
+        let n = samples.len() as f64;
+        let mean = |phase: fn(&FetchPhases) -> Duration| {
+            samples.iter().map(|(phases, _)| phase(phases).as_micros() as f64).sum::<f64>() / n
+        };
+        let process: Vec<f64> = samples
+            .iter()
+            .filter_map(|(_, process)| process.map(|d| d.as_micros() as f64))
+            .collect();
+        let first_parsed: Vec<f64> = samples
+            .iter()
+            .filter_map(|(phases, _)| phases.first_parsed.map(|d| d.as_micros() as f64))
+            .collect();
+
+        Self {
+            query_us: mean(|p| p.query),
+            transfer_us: mean(|p| p.transfer),
+            decode_us: mean(|p| p.decode),
+            decompress_us: mean(|p| p.decompress),
+            parse_us: mean(|p| p.parse),
+            process_us: if process.is_empty() { None } else { Some(process.iter().sum::<f64>() / n) },
+            first_parsed_us: if first_parsed.is_empty() {
+                None
+            } else {
+                Some(first_parsed.iter().sum::<f64>() / first_parsed.len() as f64)
+            },
+        }
+    }
+}
+
+/// One measured run of a benchmark.
+struct Sample {
+    duration: Duration,
+    records: usize,
+    phases: Option<FetchPhases>,
+    process: Option<Duration>,
+    /// Documents skipped because they did not parse.
+    failures: usize,
+}
+
+impl Sample {
+    fn new(duration: Duration, records: usize) -> Self {
+        Self {
+            duration,
+            records,
+            phases: None,
+            process: None,
+            failures: 0,
+        }
+    }
+}
+
+/// What `measure` collected over the measured iterations.
+struct Measured {
+    timing: TimingStats,
+    records: usize,
+    failures: usize,
+    phases: Option<PhaseBreakdown>,
+    counters: Option<CounterDeltas>,
+    memory: Option<MemoryStats>,
+    plans: Option<Vec<QueryPlan>>,
+}
+
+/// Nearest-rank percentile of sorted `samples`.
+fn percentile(samples: &[u64], p: f64) -> u64 {
+    let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+    samples[rank.clamp(1, samples.len()) - 1]
+}
+
+/// Runs `run` `warmup + iterations` times; each run reports the time it
+/// measured itself, how many records it processed and optionally its phases.
+/// With `explain`, the first warmup run is captured for its plans.
+async fn measure<F, Fut>(db: &Db, options: &RunOptions, mut run: F) -> anyhow::Result<Measured>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<Sample>>,
+{
+    if options.iterations == 0 {
+        anyhow::bail!("iterations must be at least 1");
+    }
+
+    // EXPLAIN would slow down a measured run, so plans need a warmup run
+    let explain = options.explain != ExplainMode::Off;
+    let options = &RunOptions {
+        warmup: if explain { options.warmup.max(1) } else { options.warmup },
+        ..*options
+    };
+    let mut plans = None;
+    for warmup in 0..options.warmup {
+        if explain && warmup == 0 {
+            plans = Some(explain::capture(db, options.explain, run()).await?);
+        } else {
+            run().await?;
+        }
+    }
+
+    let mut durations = Vec::with_capacity(options.iterations as usize);
+    let mut phases = Vec::with_capacity(options.iterations as usize);
+    let mut records = 0;
+    let mut failures = 0;
+    let mut memory = Vec::with_capacity(options.iterations as usize);
+    let mut eviction = None;
+    let mut counters = CounterWindow::open(db).await?;
+    for _ in 0..options.iterations {
+        if options.cache == CacheMode::Cold {
+            // Checking what stayed cached can be expensive, so only the
+            // first eviction does
+            let evicted = cache::evict(db, eviction.is_none()).await?;
+            if !matches!(eviction, Some(Eviction::Incomplete { .. })) {
+                eviction = Some(evicted);
+            }
+        }
+        if let Some(counters) = &mut counters {
+            counters.run_starts(db).await?;
+        }
+        let run_start = RunStart::begin();
+        let sample = run().await?;
+        memory.push(run_start.finish());
+        if let Some(counters) = &mut counters {
+            counters.run_ends(db).await?;
+        }
+        durations.push(sample.duration);
+        if let Some(fetch) = sample.phases {
+            phases.push((fetch, sample.process));
+        }
+        records = sample.records;
+        failures = sample.failures;
+    }
+
+    Ok(Measured {
+        timing: TimingStats::new(options, &durations, eviction),
+        records,
+        failures,
+        phases: if phases.is_empty() { None } else { Some(PhaseBreakdown::new(&phases)) },
+        counters: counters.map(CounterWindow::close),
+        memory: MemoryStats::new(&memory),
+        plans,
+    })
+}
+
+fn result(
+    storage_type: &str,
+    count: i32,
+    measured: Measured,
+    processing_details: Option<serde_json::Value>,
+) -> BenchmarkResult {
+    BenchmarkResult {
+        storage_type: storage_type.to_string(),
+        count,
+        duration_ms: u128::from(measured.timing.p50_us / 1000),
+        records_processed: measured.records,
+        processing_details,
+        timing: Some(measured.timing),
+        phases: measured.phases,
+        counters: measured.counters,
+        memory: measured.memory,
+        stored: None,
+        plans: measured.plans,
+    }
+}
+
+/// Reads the newest `count` users from `store` and maps them into `User`.
+pub async fn read(store: &dyn UserStore, count: i32, options: &RunOptions) -> anyhow::Result<BenchmarkResult> {
+    let measured = measure(store.db(), options, || async {
+        let start = Instant::now();
+
+        let (users, phases) = store.fetch(UserFilter::Latest { limit: count }).await?;
+
+        Ok(Sample {
+            phases: Some(phases),
+            failures: phases.skipped,
+            ..Sample::new(start.elapsed(), users.len())
+        })
+    })
+    .await?;
+
+    let details = (measured.failures > 0).then(|| serde_json::json!({ "parse_failures": measured.failures }));
+
+    Ok(BenchmarkResult {
+        stored: Some(StoredSize::of(store).await?),
+        ..result(&store.storage_type(), count, measured, details)
+    })
+}
+
+/// Filtered read timed by `filtered`, each backed by an index on
+/// `users_column` and by an expression index on the document layouts where
+/// the backend supports one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterQuery {
+    /// `count` point lookups by email.
+    Email,
+    /// Up to `count` users aged 30 to 39.
+    AgeRange,
+    /// Users per age over the whole table, grouped by the database.
+    AgeHistogram,
+}
+
+impl FilterQuery {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterQuery::Email => "email",
+            FilterQuery::AgeRange => "age_range",
+            FilterQuery::AgeHistogram => "age_histogram",
+        }
+    }
+}
+
+/// Runs `query` against `store`; `records_processed` counts users found, or
+/// histogram buckets for `AgeHistogram`.
+pub async fn filtered(
+    store: &dyn UserStore,
+    query: FilterQuery,
+    count: i32,
+    options: &RunOptions,
+) -> anyhow::Result<BenchmarkResult> {
+    let measured = measure(store.db(), options, || async {
+        let start = Instant::now();
+
+        match query {
+            FilterQuery::Email => {
+                let mut found = 0;
+                let mut phases = FetchPhases::default();
+                for i in 1..=count {
+                    // Same addresses as `data_generator::simple_user`
+                    let email = format!("user{}@example.com", i);
+                    let (users, lookup) = store.fetch(UserFilter::Email(email)).await?;
+                    found += users.len();
+                    phases += lookup;
+                }
+                Ok(Sample {
+                    phases: Some(phases),
+                    failures: phases.skipped,
+                    ..Sample::new(start.elapsed(), found)
+                })
+            }
+            FilterQuery::AgeRange => {
+                let (users, phases) = store
+                    .fetch(UserFilter::AgeRange { min: 30, max: 39, limit: count })
+                    .await?;
+                Ok(Sample {
+                    phases: Some(phases),
+                    failures: phases.skipped,
+                    ..Sample::new(start.elapsed(), users.len())
+                })
+            }
+            FilterQuery::AgeHistogram => {
+                let histogram = store.age_histogram().await?;
+                Ok(Sample::new(start.elapsed(), histogram.len()))
+            }
+        }
+    })
+    .await?;
+
+    let details = serde_json::json!({ "query": query.as_str(), "parse_failures": measured.failures });
+
+    Ok(result(&store.storage_type(), count, measured, Some(details)))
+}
+
+/// Write operation timed by `write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl WriteOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WriteOperation::Insert => "insert",
+            WriteOperation::Update => "update",
+            WriteOperation::Delete => "delete",
+        }
+    }
+}
+
+/// Times `operation` over `count` fresh rows written with `mode`.
+///
+/// The rows are separate from the generated dataset: whatever the operation
+/// does not cover (seeding before update/delete, removing inserted rows) runs
+/// untimed as a batched write, so the table is left as it was.
+pub async fn write(
+    store: &dyn UserStore,
+    operation: WriteOperation,
+    count: i32,
+    mode: WriteMode,
+    batch_size: usize,
+    options: &RunOptions,
+) -> anyhow::Result<BenchmarkResult> {
+    let measured = measure(store.db(), options, || write_once(store, operation, count, mode, batch_size)).await?;
+
+    let mean_us = measured.timing.mean_us;
+    let rows_per_sec = if mean_us > 0.0 { measured.records as f64 / (mean_us / 1_000_000.0) } else { 0.0 };
+    let details = serde_json::json!({
+        "operation": operation.as_str(),
+        "mode": mode.as_str(),
+        "batch_size": if mode == WriteMode::Batched { Some(batch_size) } else { None },
+        "rows_per_sec": rows_per_sec
+    });
+
+    Ok(result(&store.storage_type(), count, measured, Some(details)))
+}
+
+async fn write_once(
+    store: &dyn UserStore,
+    operation: WriteOperation,
+    count: i32,
+    mode: WriteMode,
+    batch_size: usize,
+) -> anyhow::Result<Sample> {
+    let mut users: Vec<User> = (1..=count).map(benchmark_user).collect();
+    let ids: Vec<_> = users.iter().map(|user| user.id).collect();
+
+    if operation != WriteOperation::Insert {
+        store.insert_rows(&users, WriteMode::Batched, batch_size).await?;
+    }
+    if operation == WriteOperation::Update {
+        for user in &mut users {
+            user.age += 1;
+        }
+    }
+
+    let start = Instant::now();
+
+    match operation {
+        WriteOperation::Insert => store.insert_rows(&users, mode, batch_size).await?,
+        WriteOperation::Update => store.update_rows(&users, mode, batch_size).await?,
+        WriteOperation::Delete => store.delete_rows(&ids, mode, batch_size).await?,
+    }
+
+    let duration = start.elapsed();
+
+    if operation != WriteOperation::Delete {
+        store.delete_rows(&ids, WriteMode::Batched, batch_size).await?;
+    }
+
+    Ok(Sample::new(duration, users.len()))
+}
+
+/// Generated user whose email cannot collide with the `generate` dataset.
+fn benchmark_user(i: i32) -> User {
+    let mut user = data_generator::simple_user(i);
+    user.email = format!("bench.{}@example.com", user.id);
+    user
+}
+
+/// What `complex_processing` parses the documents into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplexModel {
+    /// `serde_json::Value` trees, analysed with `get`/`get_mut` chains.
+    #[default]
+    Value,
+    /// `ComplexUser` structs; documents of another shape are skipped.
+    Typed,
+}
+
+impl ComplexModel {
+    pub const ALL: [ComplexModel; 2] = [ComplexModel::Value, ComplexModel::Typed];
+
+    fn storage_type(&self) -> &'static str {
+        match self {
+            ComplexModel::Value => "complex_json_processing",
+            ComplexModel::Typed => "complex_typed_processing",
+        }
+    }
+}
+
+/// Reads complex JSON documents and runs the application-side analyses on
+/// them, parsed as `model` with the store's parser.
+pub async fn complex_processing(
+    store: &dyn DocumentStore,
+    count: i32,
+    model: ComplexModel,
+    options: &RunOptions,
+) -> anyhow::Result<BenchmarkResult> {
+    let measured = measure(store.db(), options, || async {
+        let start = Instant::now();
+
+        if store.fetch_mode() == FetchMode::Streaming {
+            let streamed = match model {
+                ComplexModel::Value => stream_complex(store, count, start, process_complex).await?,
+                ComplexModel::Typed => stream_complex(store, count, start, process_complex_typed).await?,
+            };
+            return Ok(Sample {
+                phases: Some(streamed.phases),
+                process: Some(streamed.process),
+                failures: streamed.failures,
+                ..Sample::new(start.elapsed(), streamed.processed)
+            });
+        }
+
+        // Get JSON data
+        let (rows, mut phases) = store.fetch_documents(count).await?;
+        let mut first_parsed = None;
+        let mut failures = 0;
+
+        let (processed, process) = match model {
+            ComplexModel::Value => {
+                let parse_start = Instant::now();
+                let documents: Vec<serde_json::Value> = rows
+                    .into_iter()
+                    .filter_map(|data_str| store.parser().parse(data_str).map_err(|_| failures += 1).ok())
+                    .inspect(|_| {
+                        first_parsed.get_or_insert_with(|| start.elapsed());
+                    })
+                    .collect();
+                phases.parse = parse_start.elapsed();
+
+                // Execute complex processing
+                let process_start = Instant::now();
+                let processed_users: Vec<serde_json::Value> = documents.iter().map(process_complex).collect();
+                (processed_users.len(), process_start.elapsed())
+            }
+            ComplexModel::Typed => {
+                let parse_start = Instant::now();
+                let users: Vec<ComplexUser> = rows
+                    .into_iter()
+                    .filter_map(|data_str| store.parser().parse(data_str).map_err(|_| failures += 1).ok())
+                    .inspect(|_| {
+                        first_parsed.get_or_insert_with(|| start.elapsed());
+                    })
+                    .collect();
+                phases.parse = parse_start.elapsed();
+
+                let process_start = Instant::now();
+                let processed_users: Vec<ComplexUser> = users.iter().map(process_complex_typed).collect();
+                (processed_users.len(), process_start.elapsed())
+            }
+        };
+        phases.first_parsed = first_parsed;
+
+        Ok(Sample {
+            phases: Some(phases),
+            process: Some(process),
+            failures,
+            ..Sample::new(start.elapsed(), processed)
+        })
+    })
+    .await?;
+
+    // Documents that are not complex profiles (e.g. simple users in the same
+    // table) are skipped, so the models may process different rows
+    let details = serde_json::json!({
+        "engagement_calculation": "completed",
+        "tag_analysis": "completed",
+        "achievement_aggregation": "completed",
+        "text_analysis": "completed",
+        "parse_failures": measured.failures
+    });
+
+    Ok(BenchmarkResult {
+        stored: Some(StoredSize::of(store).await?),
+        ..result(&store.label(model.storage_type()), count, measured, Some(details))
+    })
+}
+
+/// Reads complex profiles from the `complex_column` tables and runs the
+/// analyses of `complex_processing` on them, like `ComplexModel::Typed`.
+pub async fn complex_column_processing(
+    store: &ComplexColumnStore,
+    count: i32,
+    options: &RunOptions,
+) -> anyhow::Result<BenchmarkResult> {
+    let measured = measure(store.db(), options, || async {
+        let start = Instant::now();
+
+        let (users, phases) = store.fetch_users(count).await?;
+
+        let process_start = Instant::now();
+        let processed_users: Vec<ComplexUser> = users.iter().map(process_complex_typed).collect();
+        let process = process_start.elapsed();
+
+        Ok(Sample {
+            phases: Some(phases),
+            process: Some(process),
+            ..Sample::new(start.elapsed(), processed_users.len())
+        })
+    })
+    .await?;
+
+    let details = serde_json::json!({
+        "engagement_calculation": "completed",
+        "tag_analysis": "completed",
+        "achievement_aggregation": "completed",
+        "text_analysis": "completed"
+    });
+
+    Ok(result("complex_column_processing", count, measured, Some(details)))
+}
+
+/// Runs the same analyses as `complex_processing` in SQL, so only the
+/// results cross the wire and nothing is parsed. The typed application-side
+/// processing of the same documents runs next to it, and
+/// `processing_details.comparison` holds both timings and both results.
+pub async fn complex_aggregation(store: &JsonStore, count: i32, options: &RunOptions) -> anyhow::Result<BenchmarkResult> {
+    const STORAGE_TYPE: &str = "complex_sql_aggregation";
+
+    let measured = measure(store.db(), options, || async {
+        let start = Instant::now();
+
+        let (aggregates, phases) = store.aggregate_complex(count).await?;
+
+        Ok(Sample {
+            phases: Some(phases),
+            ..Sample::new(start.elapsed(), aggregates.len())
+        })
+    })
+    .await?;
+    let app = complex_processing(store, count, ComplexModel::Typed, options).await?;
+
+    // One more unmeasured run of each side for the values they computed
+    let (sql_results, _) = store.aggregate_complex(count).await?;
+    let (documents, _) = store.fetch_documents(count).await?;
+    let app_results: Vec<ComplexAggregate> = documents
+        .into_iter()
+        .filter_map(|document| store.parser().parse::<ComplexUser>(document).ok())
+        .map(|user| app_aggregate(&process_complex_typed(&user)))
+        .collect();
+
+    let sql_timing = &measured.timing;
+    let app_timing = app.timing.as_ref().expect("complex_processing measures its runs");
+    let comparison = AggregationComparison {
+        sql: ComparedSide::new(STORAGE_TYPE, sql_timing, &sql_results),
+        app: ComparedSide::new(&app.storage_type, app_timing, &app_results),
+        speedup: app_timing.p50_us as f64 / sql_timing.p50_us.max(1) as f64,
+        ..AggregationComparison::compare(&sql_results, &app_results)
+    };
+
+    let details = serde_json::json!({
+        "engagement_calculation": "sql",
+        "tag_analysis": "sql",
+        "achievement_aggregation": "sql",
+        "text_analysis": "sql",
+        "comparison": comparison
+    });
+
+    let mut result = result(STORAGE_TYPE, count, measured, Some(details));
+    if let (Some(plans), Some(app_plans)) = (&mut result.plans, app.plans) {
+        plans.extend(app_plans);
+    }
+    Ok(result)
+}
+
+/// `complex_aggregation` next to the typed `complex_processing` of the same
+/// documents. The SQL side approximates `word_count` as one more than the
+/// spaces in the bio, so bios with other whitespace, or with leading,
+/// trailing or repeated spaces, show up as mismatches.
+#[derive(Debug, Default, Serialize)]
+struct AggregationComparison {
+    sql: ComparedSide,
+    app: ComparedSide,
+    /// Median of the application side over the median of the SQL side.
+    speedup: f64,
+    /// Documents both sides analysed. The SQL side also returns a row of
+    /// `NULL`s for every document that is not a complex profile.
+    documents_compared: usize,
+    documents_matching: usize,
+    /// The first values that differ, up to `MAX_MISMATCHES`.
+    mismatches: Vec<Mismatch>,
+}
+
+const MAX_MISMATCHES: usize = 10;
+
+impl AggregationComparison {
+    /// Compares the documents of `sql` and `app` by id, value by value.
+    fn compare(sql: &[ComplexAggregate], app: &[ComplexAggregate]) -> Self {
+        let app: HashMap<&str, &ComplexAggregate> =
+            app.iter().map(|aggregate| (aggregate.id.as_str(), aggregate)).collect();
+        let mut comparison = Self::default();
+        for sql in sql {
+            let Some(app) = app.get(sql.id.as_str()) else {
+                continue;
+            };
+            comparison.documents_compared += 1;
+            let mut matching = true;
+            for ((field, sql_value), (_, app_value)) in aggregate_values(sql).into_iter().zip(aggregate_values(app)) {
+                if same_value(sql_value, app_value) {
+                    continue;
+                }
+                matching = false;
+                if comparison.mismatches.len() < MAX_MISMATCHES {
+                    comparison.mismatches.push(Mismatch {
+                        id: sql.id.clone(),
+                        field,
+                        sql: sql_value,
+                        app: app_value,
+                    });
+                }
+            }
+            if matching {
+                comparison.documents_matching += 1;
+            }
+        }
+        comparison
+    }
+}
+
+/// Timing and results of one side of an `AggregationComparison`.
+#[derive(Debug, Default, Serialize)]
+struct ComparedSide {
+    storage_type: String,
+    p50_us: u64,
+    mean_us: f64,
+    /// Documents with results, i.e. complex profiles.
+    documents: usize,
+    /// Mean of each value over those documents.
+    means: BTreeMap<&'static str, f64>,
+}
+
+impl ComparedSide {
+    fn new(storage_type: &str, timing: &TimingStats, results: &[ComplexAggregate]) -> Self {
+        let analysed: Vec<&ComplexAggregate> =
+            results.iter().filter(|aggregate| aggregate.total_tags.is_some()).collect();
+        let mut means = BTreeMap::new();
+        for aggregate in &analysed {
+            for (field, value) in aggregate_values(aggregate) {
+                *means.entry(field).or_insert(0.0) += value.unwrap_or(0.0);
+            }
+        }
+        for sum in means.values_mut() {
+            *sum /= analysed.len() as f64;
+        }
+        Self {
+            storage_type: storage_type.to_string(),
+            p50_us: timing.p50_us,
+            mean_us: timing.mean_us,
+            documents: analysed.len(),
+            means,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Mismatch {
+    id: String,
+    field: &'static str,
+    sql: Option<f64>,
+    app: Option<f64>,
+}
+
+/// The values `process_complex_typed` added to `user`, as the SQL side
+/// returns them.
+fn app_aggregate(user: &ComplexUser) -> ComplexAggregate {
+    let tags = user.metadata.tag_analysis.as_ref();
+    let bio = user.profile.bio_analysis.as_ref();
+    ComplexAggregate {
+        id: user.id.to_string(),
+        engagement_rate: user.profile.statistics.engagement_rate,
+        total_tags: tags.map(|tags| tags.total_tags as i64),
+        verified_tags: tags.map(|tags| tags.verified_tags as i64),
+        verification_rate: tags.map(|tags| tags.verification_rate),
+        achievement_points: user.profile.total_achievement_points.map(|points| points as i64),
+        word_count: bio.map(|bio| bio.word_count as i64),
+        char_count: bio.map(|bio| bio.char_count as i64),
+        sentence_count: bio.map(|bio| bio.sentence_count as i64),
+        avg_words_per_sentence: bio.map(|bio| bio.avg_words_per_sentence),
+    }
+}
+
+/// Every value of `aggregate`, by name.
+fn aggregate_values(aggregate: &ComplexAggregate) -> [(&'static str, Option<f64>); 9] {
+    let count = |value: Option<i64>| value.map(|value| value as f64);
+    [
+        ("engagement_rate", aggregate.engagement_rate),
+        ("total_tags", count(aggregate.total_tags)),
+        ("verified_tags", count(aggregate.verified_tags)),
+        ("verification_rate", aggregate.verification_rate),
+        ("achievement_points", count(aggregate.achievement_points)),
+        ("word_count", count(aggregate.word_count)),
+        ("char_count", count(aggregate.char_count)),
+        ("sentence_count", count(aggregate.sentence_count)),
+        ("avg_words_per_sentence", aggregate.avg_words_per_sentence),
+    ]
+}
+
+/// Equal up to floating-point rounding, which differs between SQL's
+/// `posts * 100.0 / followers` and Rust's `posts / followers * 100.0`.
+fn same_value(sql: Option<f64>, app: Option<f64>) -> bool {
+    match (sql, app) {
+        (Some(sql), Some(app)) => (sql - app).abs() <= 1e-9 * sql.abs().max(app.abs()).max(1.0),
+        (sql, app) => sql == app,
+    }
+}
+
+/// What `stream_complex` did with the documents.
+struct Streamed {
+    phases: FetchPhases,
+    processed: usize,
+    failures: usize,
+    process: Duration,
+}
+
+/// Streaming `complex_processing`: each document is parsed and processed as
+/// it arrives, so only the processed results are held.
+async fn stream_complex<T: DeserializeOwned + Send>(
+    store: &dyn DocumentStore,
+    count: i32,
+    start: Instant,
+    process: fn(&T) -> T,
+) -> anyhow::Result<Streamed> {
+    let parser = store.parser();
+    let mut processed = Vec::new();
+    let mut failures = 0;
+    let mut parse = Duration::ZERO;
+    let mut process_time = Duration::ZERO;
+    let mut first_parsed = None;
+
+    let mut phases = store
+        .stream_documents(count, &mut |document| {
+            let parse_start = Instant::now();
+            let parsed = parser.parse::<T>(document);
+            parse += parse_start.elapsed();
+
+            match parsed {
+                Ok(parsed) => {
+                    first_parsed.get_or_insert_with(|| start.elapsed());
+                    let process_start = Instant::now();
+                    processed.push(process(&parsed));
+                    process_time += process_start.elapsed();
+                }
+                Err(_) => failures += 1,
+            }
+            Ok(())
+        })
+        .await?;
+    phases.parse = parse;
+    phases.first_parsed = first_parsed;
+
+    Ok(Streamed {
+        phases,
+        processed: processed.len(),
+        failures,
+        process: process_time,
+    })
+}
+
+fn process_complex(user_data: &serde_json::Value) -> serde_json::Value {
+    // Simulate complex processing
+    let mut processed_user = user_data.clone();
+
+    // 1. Calculate statistics
+    if let Some(profile) = processed_user.get_mut("profile") {
+        if let Some(stats) = profile.get_mut("statistics") {
+            if let Some(posts) = stats.get("posts_count").and_then(|v| v.as_u64()) {
+                if let Some(followers) = stats.get("followers_count").and_then(|v| v.as_u64()) {
+                    let engagement_rate = if followers > 0 {
+                        (posts as f64 / followers as f64) * 100.0
+                    } else {
+                        0.0
+                    };
+                    stats["engagement_rate"] = serde_json::json!(engagement_rate);
+                }
+            }
+        }
+    }
+
+    // 2. Tag analysis
+    if let Some(metadata) = processed_user.get_mut("metadata") {
+        if let Some(tags) = metadata.get("tags").and_then(|v| v.as_array()) {
+            let tag_count = tags.len();
+            let verified_tags = tags.iter().filter(|tag| tag.as_str() == Some("verified")).count();
+            metadata["tag_analysis"] = serde_json::json!({
+                "total_tags": tag_count,
+                "verified_tags": verified_tags,
+                "verification_rate": if tag_count > 0 { (verified_tags as f64 / tag_count as f64) * 100.0 } else { 0.0 }
+            });
+        }
+    }
+
+    // 3. Achievement aggregation
+    if let Some(profile) = processed_user.get("profile") {
+        if let Some(achievements) = profile.get("achievements").and_then(|v| v.as_array()) {
+            let total_points: u64 = achievements.iter()
+                .filter_map(|achievement| achievement.get("points").and_then(|v| v.as_u64()))
+                .sum();
+
+            if let Some(profile_mut) = processed_user.get_mut("profile") {
+                profile_mut["total_achievement_points"] = serde_json::json!(total_points);
+            }
+        }
+    }
+
+    // 4. Complex string processing
+    if let Some(profile) = processed_user.get("profile") {
+        if let Some(bio) = profile.get("bio").and_then(|v| v.as_str()) {
+            let word_count = bio.split_whitespace().count();
+            let char_count = bio.chars().count();
+            let sentence_count = bio.split('.').count() - 1;
+
+            if let Some(profile_mut) = processed_user.get_mut("profile") {
+                profile_mut["bio_analysis"] = serde_json::json!({
+                    "word_count": word_count,
+                    "char_count": char_count,
+                    "sentence_count": sentence_count,
+                    "avg_words_per_sentence": if sentence_count > 0 { word_count as f64 / sentence_count as f64 } else { 0.0 }
+                });
+            }
+        }
+    }
+
+    processed_user
+}
+
+/// `process_complex` on the typed model: the same four analyses, stored in
+/// the `Option` fields the stored documents leave empty.
+fn process_complex_typed(user: &ComplexUser) -> ComplexUser {
+    let mut processed_user = user.clone();
+
+    // 1. Calculate statistics
+    let stats = &mut processed_user.profile.statistics;
+    stats.engagement_rate = Some(if stats.followers_count > 0 {
+        (stats.posts_count as f64 / stats.followers_count as f64) * 100.0
+    } else {
+        0.0
+    });
+
+    // 2. Tag analysis
+    let tags = &processed_user.metadata.tags;
+    let tag_count = tags.len();
+    let verified_tags = tags.iter().filter(|tag| *tag == "verified").count();
+    processed_user.metadata.tag_analysis = Some(TagAnalysis {
+        total_tags: tag_count,
+        verified_tags,
+        verification_rate: if tag_count > 0 { (verified_tags as f64 / tag_count as f64) * 100.0 } else { 0.0 },
+    });
+
+    // 3. Achievement aggregation
+    let total_points = processed_user.profile.achievements.iter().map(|achievement| achievement.points).sum();
+    processed_user.profile.total_achievement_points = Some(total_points);
+
+    // 4. Complex string processing
+    let bio = &processed_user.profile.bio;
+    let word_count = bio.split_whitespace().count();
+    let char_count = bio.chars().count();
+    let sentence_count = bio.split('.').count() - 1;
+    processed_user.profile.bio_analysis = Some(BioAnalysis {
+        word_count,
+        char_count,
+        sentence_count,
+        avg_words_per_sentence: if sentence_count > 0 { word_count as f64 / sentence_count as f64 } else { 0.0 },
+    });
+
+    processed_user
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(micros: &[u64]) -> TimingStats {
+        let durations: Vec<Duration> = micros.iter().map(|&us| Duration::from_micros(us)).collect();
+        TimingStats::new(&RunOptions::default(), &durations, None)
+    }
+
+    #[test]
+    fn percentile_of_one_sample_is_that_sample() {
+        for p in [0.0, 50.0, 95.0, 99.0, 100.0] {
+            assert_eq!(percentile(&[42], p), 42);
+        }
+    }
+
+    #[test]
+    fn percentile_of_two_samples_takes_the_nearest_rank() {
+        assert_eq!(percentile(&[10, 20], 50.0), 10);
+        assert_eq!(percentile(&[10, 20], 51.0), 20);
+        assert_eq!(percentile(&[10, 20], 95.0), 20);
+        assert_eq!(percentile(&[10, 20], 99.0), 20);
+    }
+
+    #[test]
+    fn percentile_of_a_hundred_samples_is_the_rank() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 0.0), 1);
+        assert_eq!(percentile(&samples, 50.0), 50);
+        assert_eq!(percentile(&samples, 95.0), 95);
+        assert_eq!(percentile(&samples, 99.0), 99);
+        assert_eq!(percentile(&samples, 100.0), 100);
+    }
+
+    #[test]
+    fn one_sample_has_no_spread() {
+        let stats = timing(&[500]);
+        assert_eq!((stats.min_us, stats.max_us, stats.p50_us, stats.p99_us), (500, 500, 500, 500));
+        assert_eq!(stats.mean_us, 500.0);
+        assert_eq!(stats.stddev_us, 0.0);
+        assert!(!stats.high_variance);
+    }
+
+    #[test]
+    fn two_samples_use_the_sample_stddev() {
+        let stats = timing(&[20, 10]);
+        assert_eq!((stats.min_us, stats.max_us, stats.p50_us, stats.p95_us), (10, 20, 10, 20));
+        assert_eq!(stats.mean_us, 15.0);
+        assert!((stats.stddev_us - 50f64.sqrt()).abs() < 1e-9);
+        assert!(stats.high_variance);
+    }
+
+    #[test]
+    fn a_hundred_samples_spread_over_their_ranks() {
+        let micros: Vec<u64> = (1..=100).rev().collect();
+        let stats = timing(&micros);
+        assert_eq!((stats.min_us, stats.max_us), (1, 100));
+        assert_eq!((stats.p50_us, stats.p95_us, stats.p99_us), (50, 95, 99));
+        assert_eq!(stats.mean_us, 50.5);
+        // Sum of squared deviations of 1..=100 is 83325, over n - 1
+        assert!((stats.stddev_us - (83325.0f64 / 99.0).sqrt()).abs() < 1e-9);
+        assert!(stats.high_variance);
+    }
+
+    #[test]
+    fn constant_series_has_no_variance() {
+        let stats = timing(&[250; 10]);
+        assert_eq!((stats.min_us, stats.max_us, stats.p50_us, stats.p99_us), (250, 250, 250, 250));
+        assert_eq!(stats.stddev_us, 0.0);
+        assert!(!stats.high_variance);
+    }
+
+    #[test]
+    fn high_variance_above_a_tenth_of_the_mean() {
+        // CV of about 0.067
+        assert!(!timing(&[100, 110]).high_variance);
+        // CV of about 0.108
+        assert!(timing(&[100, 100, 120]).high_variance);
+    }
+}
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use sqlx::postgres::PgPool;
+use sqlx::{Connection, Executor, Row};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::db::Db;
+
+/// Whether the measured runs of a benchmark find the table pages cached,
+/// taken from `?cache=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// Pages stay cached as the previous runs and the warmup left them.
+    #[default]
+    Warm,
+    /// Cached pages are evicted before every measured run, as far as the
+    /// backend allows (see `evict`).
+    Cold,
+}
+
+impl CacheMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheMode::Warm => "warm",
+            CacheMode::Cold => "cold",
+        }
+    }
+}
+
+impl FromStr for CacheMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warm" => Ok(CacheMode::Warm),
+            "cold" => Ok(CacheMode::Cold),
+            _ => anyhow::bail!("unknown cache mode: {} (expected warm or cold)", s),
+        }
+    }
+}
+
+/// How long SQLite eviction waits for released connections to come back to
+/// the pool.
+const RELEASE_GRACE: Duration = Duration::from_millis(100);
+
+/// How long MySQL eviction waits for each buffer pool resize.
+const RESIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What `evict` achieved before a measured run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Eviction {
+    /// None of the database's pages were left in the caches `evict` reaches.
+    Evicted,
+    /// Some or all of them stayed cached, for `reason`.
+    Incomplete { reason: String },
+}
+
+impl Eviction {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Eviction::Evicted)
+    }
+}
+
+/// Evicts as much of the cached table data as a client can and, with
+/// `verify`, checks what is left:
+///
+/// - SQLite: closes every idle connection, dropping its page cache, and
+///   opens a fresh one with memory-mapped I/O off so that reads go through
+///   the new page cache. A connection busy with another request keeps its
+///   cache, and an in-memory database cannot be evicted at all.
+/// - MySQL/MariaDB: `FLUSH TABLES`, which closes every open table, then
+///   shrinks the InnoDB buffer pool to its minimum and restores it (see
+///   `cycle_buffer_pool`). Verifying counts the database's pages left in the
+///   buffer pool (`information_schema.INNODB_BUFFER_PAGE`, which needs
+///   `PROCESS` and scans the whole pool).
+/// - PostgreSQL 17+: the database's shared buffers, with
+///   `pg_buffercache_evict` from the `pg_buffercache` extension when it is
+///   installed. Verifying counts the buffers of user tables that stayed
+///   (pinned or in use). Older servers keep them.
+///
+/// A step the server refuses makes the eviction `Incomplete` rather than an
+/// error. The operating system's page cache is left alone.
+pub async fn evict(db: &Db, verify: bool) -> anyhow::Result<Eviction> {
+    let eviction = match db {
+        Db::Sqlite(pool) => {
+            let file: String = sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+                .fetch_one(pool)
+                .await?;
+            if file.is_empty() {
+                return Ok(incomplete("an in-memory SQLite database is its own page cache"));
+            }
+
+            // Every connection has its own page cache, so close them all and
+            // let the pool open a new one. Released connections return to the
+            // pool in the background, hence the grace period; one held by
+            // another request is not waited for
+            let deadline = Instant::now() + RELEASE_GRACE;
+            while pool.num_idle() < pool.size() as usize && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            while let Some(connection) = pool.try_acquire() {
+                connection.detach().close().await?;
+            }
+            let busy = pool.size();
+            if busy > 0 {
+                return Ok(incomplete(format!(
+                    "{} connections busy with other requests kept their page cache",
+                    busy
+                )));
+            }
+            let mut fresh = pool.acquire().await?;
+            fresh.execute("PRAGMA mmap_size = 0").await?;
+            Eviction::Evicted
+        }
+        Db::MySql(pool) => {
+            if let Err(err) = pool.execute("FLUSH TABLES").await {
+                return Ok(incomplete(format!("FLUSH TABLES failed (needs RELOAD): {}", err)));
+            }
+            if let Err(err) = cycle_buffer_pool(pool).await {
+                return Ok(incomplete(format!(
+                    "could not resize the InnoDB buffer pool (needs SYSTEM_VARIABLES_ADMIN or SUPER): {}",
+                    err
+                )));
+            }
+            if !verify {
+                return Ok(Eviction::Evicted);
+            }
+            mysql_cached_pages(pool).await
+        }
+        Db::Postgres(pool) => {
+            let evict: Option<String> = sqlx::query_scalar("SELECT CAST(to_regproc('pg_buffercache_evict') AS TEXT)")
+                .fetch_one(pool)
+                .await?;
+            if evict.is_none() {
+                return Ok(incomplete(
+                    "pg_buffercache_evict is not available (needs PostgreSQL 17+ and the pg_buffercache extension)",
+                ));
+            }
+            sqlx::query(
+                r#"
+                SELECT pg_buffercache_evict(bufferid)
+                FROM pg_buffercache
+                WHERE reldatabase = (SELECT oid FROM pg_database WHERE datname = current_database())
+                "#,
+            )
+            .execute(pool)
+            .await?;
+            if !verify {
+                return Ok(Eviction::Evicted);
+            }
+            postgres_cached_buffers(pool).await?
+        }
+    };
+    Ok(eviction)
+}
+
+/// Shrinks the InnoDB buffer pool to its minimum and restores its size.
+/// Shrinking evicts the pages of every chunk it withdraws; the server keeps
+/// at least one chunk per buffer pool instance, and the pages in those stay.
+/// The resize applies to the whole server.
+async fn cycle_buffer_pool(pool: &MySqlPool) -> anyhow::Result<()> {
+    let size: u64 = sqlx::query_scalar("SELECT CAST(@@GLOBAL.innodb_buffer_pool_size AS UNSIGNED)")
+        .fetch_one(pool)
+        .await?;
+    // Below the minimum, the server rounds up to its smallest size
+    let shrunk = resize_buffer_pool(pool, 0).await;
+    let restored = resize_buffer_pool(pool, size).await;
+    shrunk.and(restored)
+}
+
+/// Sets `innodb_buffer_pool_size` and waits until InnoDB has resized the
+/// pool to the size the server settled on, which happens in the background.
+async fn resize_buffer_pool(pool: &MySqlPool, size: u64) -> anyhow::Result<()> {
+    // Unprepared, since not every MySQL version prepares SET GLOBAL
+    let sql = format!("SET GLOBAL innodb_buffer_pool_size = {}", size);
+    pool.execute(sql.as_str()).await?;
+    let (target, page_size): (u64, u64) = sqlx::query_as(
+        "SELECT CAST(@@GLOBAL.innodb_buffer_pool_size AS UNSIGNED), CAST(@@GLOBAL.innodb_page_size AS UNSIGNED)",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let deadline = Instant::now() + RESIZE_TIMEOUT;
+    loop {
+        let status = pool.fetch_one("SHOW GLOBAL STATUS LIKE 'Innodb_buffer_pool_pages_total'").await?;
+        let pages: u64 = status.get::<String, _>(1).parse()?;
+        // Every chunk spends a few of its pages on the page descriptors
+        if (pages * page_size).abs_diff(target) <= target / 20 {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                "buffer pool still holds {} pages after {:?}, expected about {}",
+                pages,
+                RESIZE_TIMEOUT,
+                target / page_size
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Database pages left in the InnoDB buffer pool.
+async fn mysql_cached_pages(pool: &MySqlPool) -> Eviction {
+    let cached: Result<i64, _> = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM information_schema.INNODB_BUFFER_PAGE
+        WHERE TABLE_NAME LIKE CONCAT('`', DATABASE(), '`.%')
+        "#,
+    )
+    .fetch_one(pool)
+    .await;
+    match cached {
+        Ok(0) => Eviction::Evicted,
+        Ok(pages) => incomplete(format!(
+            "InnoDB kept {} pages of the database in the chunks a shrunk buffer pool keeps",
+            pages
+        )),
+        Err(err) => incomplete(format!("could not inspect the InnoDB buffer pool: {}", err)),
+    }
+}
+
+fn incomplete(reason: impl Into<String>) -> Eviction {
+    Eviction::Incomplete { reason: reason.into() }
+}
+
+/// Shared buffers still holding pages of user tables, their indexes or
+/// TOAST tables (`oid` 16384 and up); catalog pages come back as soon as
+/// the next statement is planned.
+async fn postgres_cached_buffers(pool: &PgPool) -> anyhow::Result<Eviction> {
+    let cached: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM pg_buffercache
+        WHERE reldatabase = (SELECT oid FROM pg_database WHERE datname = current_database())
+          AND relfilenode IN (SELECT pg_relation_filenode(oid) FROM pg_class WHERE oid >= 16384)
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(if cached == 0 {
+        Eviction::Evicted
+    } else {
+        incomplete(format!("{} shared buffers of user tables could not be evicted", cached))
+    })
+}
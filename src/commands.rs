@@ -0,0 +1,441 @@
+use std::path::Path;
+
+use crate::benchmark::{self, BenchmarkResult, ComplexModel, RunOptions};
+use crate::cache::Eviction;
+use crate::compression::Compression;
+use crate::config::WriteConfig;
+use crate::data_generator::{self, Dataset};
+use crate::db::{Backend, Db};
+use crate::memory::MemoryStats;
+use crate::parser::JsonParser;
+use crate::stats::{self, SessionCounters};
+use crate::store::{self, ComplexColumnStore, FetchMode, JsonStore, Layout, ReadOptions};
+
+pub async fn generate(db: &Db, dataset: Dataset, count: i32, write: &WriteConfig) -> anyhow::Result<()> {
+    let (table, stats) = match dataset {
+        Dataset::Users(layout) => {
+            let store = store::for_layout(layout, db.clone())?;
+            let stats = data_generator::generate_users(store.as_ref(), count, write).await?;
+            (layout.table(), stats)
+        }
+        Dataset::Complex(compression) => {
+            let store = store::documents_for(compression, db.clone(), JsonParser::default(), FetchMode::default());
+            let stats = data_generator::generate_complex(store.as_ref(), count, write).await?;
+            (store.layout().table(), stats)
+        }
+        Dataset::ComplexColumn => {
+            let store = ComplexColumnStore::new(db.clone());
+            let stats = data_generator::generate_complex_column(&store, count, write).await?;
+            ("complex_column", stats)
+        }
+    };
+
+    println!(
+        "✅ Generated {} records in {} ({}ms, {:.0} rows/sec, batch={} transaction={})",
+        count, table, stats.duration_ms, stats.rows_per_sec, stats.batch_size, stats.transaction_size
+    );
+    Ok(())
+}
+
+pub async fn bench(db: &Db, sizes: &[i32], output: &Path, runs: &RunOptions) -> anyhow::Result<()> {
+    println!(
+        "🏃 Running benchmarks ({} iterations, {} warmup, {} cache)...",
+        runs.iterations,
+        runs.warmup,
+        runs.cache.as_str()
+    );
+    let db = &db.single_session().await?;
+
+    let layouts: Vec<Layout> = Layout::ALL
+        .into_iter()
+        .filter(|layout| layout.is_supported_by(db.backend()))
+        .collect();
+    let complex_column_store = ComplexColumnStore::new(db.clone());
+    let mut results = Vec::new();
+
+    for &count in sizes {
+        println!("\n📊 {} records", count);
+
+        for &layout in &layouts {
+            for read in ReadOptions::variants(layout) {
+                let store = store::for_layout_with(layout, db.clone(), read)?;
+                let result = benchmark::read(store.as_ref(), count, runs).await?;
+                print_result(&result);
+                results.push(result);
+            }
+        }
+
+        // Every parser and compression buffered, and the default ones streamed
+        let mut document_stores: Vec<_> = Compression::ALL
+            .into_iter()
+            .flat_map(|compression| {
+                JsonParser::ALL
+                    .iter()
+                    .map(move |&parser| store::documents_for(compression, db.clone(), parser, FetchMode::Buffered))
+            })
+            .collect();
+        document_stores.push(store::documents_for(
+            Compression::None,
+            db.clone(),
+            JsonParser::default(),
+            FetchMode::Streaming,
+        ));
+        for documents in &document_stores {
+            for model in ComplexModel::ALL {
+                let result = benchmark::complex_processing(documents.as_ref(), count, model, runs).await?;
+                print_result(&result);
+                results.push(result);
+            }
+        }
+
+        let result = benchmark::complex_aggregation(&JsonStore::new(db.clone()), count, runs).await?;
+        print_result(&result);
+        results.push(result);
+
+        let result = benchmark::complex_column_processing(&complex_column_store, count, runs).await?;
+        print_result(&result);
+        results.push(result);
+    }
+
+    std::fs::write(output, serde_json::to_string_pretty(&results)?)?;
+    println!("\n🏁 Benchmark completed! Results saved to {}", output.display());
+    Ok(())
+}
+
+fn print_result(result: &BenchmarkResult) {
+    match &result.timing {
+        Some(timing) => println!(
+            "  {}: p50 {}µs, p95 {}µs, mean {:.0}µs ± {:.0}µs, {} records processed{}",
+            result.storage_type,
+            timing.p50_us,
+            timing.p95_us,
+            timing.mean_us,
+            timing.stddev_us,
+            result.records_processed,
+            if timing.high_variance { " ⚠️ high variance" } else { "" }
+        ),
+        None => println!(
+            "  {}: {}ms, {} records processed",
+            result.storage_type, result.duration_ms, result.records_processed
+        ),
+    }
+    if let Some(Eviction::Incomplete { reason }) = result.timing.as_ref().and_then(|timing| timing.eviction.as_ref()) {
+        println!("    ⚠️ not a cold run: {}", reason);
+    }
+    if let Some(phases) = &result.phases {
+        print!(
+            "    query {:.0}µs, transfer {:.0}µs, decode {:.0}µs, decompress {:.0}µs, parse {:.0}µs",
+            phases.query_us, phases.transfer_us, phases.decode_us, phases.decompress_us, phases.parse_us
+        );
+        if let Some(process_us) = phases.process_us {
+            print!(", process {:.0}µs", process_us);
+        }
+        match phases.first_parsed_us {
+            Some(first_parsed_us) => println!(", first row {:.0}µs", first_parsed_us),
+            None => println!(),
+        }
+    }
+    if let Some(memory) = &result.memory {
+        let mut figures = Vec::new();
+        if let (Some(allocated_bytes), Some(allocations)) = (memory.allocated_bytes, memory.allocations) {
+            figures.push(format!(
+                "allocated {} in {} allocations",
+                format_bytes(allocated_bytes as i64),
+                allocations
+            ));
+        }
+        if let Some(peak_live_bytes) = memory.peak_live_bytes {
+            figures.push(format!("peak live +{}", format_bytes(peak_live_bytes as i64)));
+        }
+        if let Some(peak_rss_bytes) = memory.peak_rss_bytes {
+            figures.push(format!("peak RSS +{}", format_bytes(peak_rss_bytes as i64)));
+        }
+        println!("    {}", figures.join(", "));
+    }
+    let parse_failures = result
+        .processing_details
+        .as_ref()
+        .and_then(|details| details["parse_failures"].as_u64())
+        .unwrap_or(0);
+    if parse_failures > 0 {
+        println!("    ⚠️ {} documents did not parse and were skipped", parse_failures);
+    }
+    if let Some(comparison) = result.processing_details.as_ref().map(|details| &details["comparison"]) {
+        if comparison.is_object() {
+            println!(
+                "    {} p50 {}µs vs SQL p50 {}µs ({:.1}x), {} of {} documents match",
+                comparison["app"]["storage_type"].as_str().unwrap_or("app"),
+                comparison["app"]["p50_us"],
+                comparison["sql"]["p50_us"],
+                comparison["speedup"].as_f64().unwrap_or(0.0),
+                comparison["documents_matching"],
+                comparison["documents_compared"]
+            );
+        }
+    }
+    if let Some(counters) = &result.counters {
+        println!("    {}", changed_counters(&counters.session));
+        println!("    server-wide: {}", changed_counters(&counters.server));
+    }
+}
+
+fn changed_counters(counters: &SessionCounters) -> String {
+    let changed: Vec<String> = counters
+        .iter()
+        .filter(|(_, delta)| **delta != 0)
+        .map(|(name, delta)| format!("{} {}", name, delta))
+        .collect();
+    changed.join(", ")
+}
+
+pub async fn reset(db: &Db) -> anyhow::Result<()> {
+    for layout in Layout::ALL {
+        if !layout.is_supported_by(db.backend()) {
+            continue;
+        }
+        store::for_layout(layout, db.clone())?.clear().await?;
+        println!("🗑️ Cleared {}", layout.table());
+    }
+    ComplexColumnStore::new(db.clone()).clear().await?;
+    println!("🗑️ Cleared complex_column");
+    Ok(())
+}
+
+/// Prints the footprint of every layout table as reported by the server,
+/// with a total per layout.
+pub async fn storage(db: &Db, analyze: bool) -> anyhow::Result<()> {
+    let report = stats::storage(db, analyze).await?;
+
+    println!("| Layout | Table | Rows | Avg row | Data | Index | Total |");
+    println!("|--------|-------|------|---------|------|-------|-------|");
+    for table in &report.tables {
+        println!(
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            table.layout,
+            table.table,
+            format_count(table.rows),
+            format_bytes(table.avg_row_length),
+            format_bytes(table.data_length),
+            format_bytes(table.index_length),
+            format_bytes(table.total_length())
+        );
+    }
+
+    let mut layouts: Vec<(&str, i64)> = Vec::new();
+    for table in &report.tables {
+        match layouts.iter_mut().find(|(layout, _)| *layout == table.layout) {
+            Some((_, total)) => *total += table.total_length(),
+            None => layouts.push((table.layout, table.total_length())),
+        }
+    }
+    println!("\n| Layout | Total |");
+    println!("|--------|-------|");
+    for (layout, total) in layouts {
+        println!("| {} | {} |", layout, format_bytes(total));
+    }
+
+    // SQLite counts rows and pages directly
+    if !analyze && db.backend() != Backend::Sqlite {
+        println!("\nRow counts and sizes may be stale; pass --analyze to refresh the statistics first");
+    }
+    Ok(())
+}
+
+/// Renders saved results like the README table: one row per count, one
+/// column per storage type (median duration), winner among the plain read
+/// benchmarks.
+pub fn report(input: &Path) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(input)?;
+    let results: Vec<BenchmarkResult> = serde_json::from_str(&contents)?;
+
+    let mut counts: Vec<i32> = Vec::new();
+    let mut storage_types: Vec<&str> = Vec::new();
+    for result in &results {
+        if !counts.contains(&result.count) {
+            counts.push(result.count);
+        }
+        if !storage_types.contains(&result.storage_type.as_str()) {
+            storage_types.push(&result.storage_type);
+        }
+    }
+
+    println!("| Count | {} | Winner |", storage_types.join(" | "));
+    println!("|-------|{}--------|", "------|".repeat(storage_types.len()));
+
+    for count in counts {
+        let row: Vec<&BenchmarkResult> = results.iter().filter(|r| r.count == count).collect();
+        let cells: Vec<String> = storage_types
+            .iter()
+            .map(|storage_type| {
+                row.iter()
+                    .find(|r| r.storage_type == *storage_type)
+                    .map(|r| match &r.timing {
+                        Some(timing) if timing.high_variance => format!("{}ms*", r.duration_ms),
+                        _ => format!("{}ms", r.duration_ms),
+                    })
+                    .unwrap_or_else(|| "-".to_string())
+            })
+            .collect();
+        let winner = row
+            .iter()
+            .filter(|r| r.processing_details.is_none())
+            .min_by_key(|r| r.duration_ms)
+            .map(|r| r.storage_type.as_str())
+            .unwrap_or("-");
+
+        println!("| {} | {} | {} |", format_count(count.into()), cells.join(" | "), winner);
+    }
+
+    if results.iter().any(|r| r.timing.as_ref().is_some_and(|timing| timing.high_variance)) {
+        println!("\n\\* high variance (stddev above 10% of the mean)");
+    }
+
+    // Stored size next to the decode, decompress and parse cost of the largest read
+    let reads: Vec<&BenchmarkResult> = storage_types
+        .iter()
+        .filter_map(|storage_type| {
+            results
+                .iter()
+                .filter(|r| r.storage_type == *storage_type && r.stored.is_some())
+                .max_by_key(|r| r.count)
+        })
+        .collect();
+    if !reads.is_empty() {
+        println!("\n| Storage | Bytes/user | Count | Decode | Decompress | Parse |");
+        println!("|---------|------------|-------|--------|------------|-------|");
+        for r in reads {
+            let stored = r.stored.as_ref().map_or(0.0, |stored| stored.bytes_per_user);
+            let phases = match &r.phases {
+                Some(phases) => format!(
+                    "{:.0}µs | {:.0}µs | {:.0}µs",
+                    phases.decode_us, phases.decompress_us, phases.parse_us
+                ),
+                None => "- | - | -".to_string(),
+            };
+            println!("| {} | {:.0} | {} | {} |", r.storage_type, stored, format_count(r.count.into()), phases);
+        }
+    }
+
+    // Streaming next to the buffered run of the same storage and count
+    let streamed: Vec<(&BenchmarkResult, &BenchmarkResult)> = results
+        .iter()
+        .filter_map(|streaming| {
+            let buffered = streaming.storage_type.strip_suffix("+streaming")?;
+            let buffered = results
+                .iter()
+                .find(|r| r.storage_type == buffered && r.count == streaming.count)?;
+            Some((buffered, streaming))
+        })
+        .collect();
+    if !streamed.is_empty() {
+        println!("\n| Storage | Count | Duration | First row | Peak RSS |");
+        println!("|---------|-------|----------|-----------|----------|");
+        for r in streamed.into_iter().flat_map(|(buffered, streaming)| [buffered, streaming]) {
+            let first_parsed = r
+                .phases
+                .as_ref()
+                .and_then(|phases| phases.first_parsed_us)
+                .map_or("-".to_string(), |first_parsed_us| format!("{:.0}µs", first_parsed_us));
+            let memory = memory_figure(r, |memory| memory.peak_rss_bytes);
+            println!(
+                "| {} | {} | {}ms | {} | {} |",
+                r.storage_type,
+                format_count(r.count.into()),
+                r.duration_ms,
+                first_parsed,
+                memory
+            );
+        }
+    }
+
+    // Memory of the largest run of each storage type
+    let measured: Vec<&BenchmarkResult> = storage_types
+        .iter()
+        .filter_map(|storage_type| {
+            results
+                .iter()
+                .filter(|r| r.storage_type == *storage_type && r.memory.is_some())
+                .max_by_key(|r| r.count)
+        })
+        .collect();
+    if !measured.is_empty() {
+        println!("\n| Storage | Count | Allocated | Allocations | Peak live | Peak RSS |");
+        println!("|---------|-------|-----------|-------------|-----------|----------|");
+        for r in measured {
+            let allocations = r
+                .memory
+                .as_ref()
+                .and_then(|memory| memory.allocations)
+                .map_or("-".to_string(), |allocations| format_count(allocations as i64));
+            println!(
+                "| {} | {} | {} | {} | {} | {} |",
+                r.storage_type,
+                format_count(r.count.into()),
+                memory_figure(r, |memory| memory.allocated_bytes),
+                allocations,
+                memory_figure(r, |memory| memory.peak_live_bytes),
+                memory_figure(r, |memory| memory.peak_rss_bytes)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// One byte figure of `result.memory`, or `-` where it was not measured.
+fn memory_figure(result: &BenchmarkResult, figure: fn(&MemoryStats) -> Option<u64>) -> String {
+    result
+        .memory
+        .as_ref()
+        .and_then(figure)
+        .map_or("-".to_string(), |bytes| format_bytes(bytes as i64))
+}
+
+fn format_count(count: i64) -> String {
+    let digits = count.unsigned_abs().to_string();
+    let mut formatted = String::from(if count < 0 { "-" } else { "" });
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_count_groups_thousands() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1000), "1,000");
+        assert_eq!(format_count(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn format_count_keeps_the_sign_out_of_the_groups() {
+        assert_eq!(format_count(-1), "-1");
+        assert_eq!(format_count(-100), "-100");
+        assert_eq!(format_count(-1000), "-1,000");
+        assert_eq!(format_count(-123456), "-123,456");
+        assert_eq!(format_count(i64::MIN), "-9,223,372,036,854,775,808");
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// Application-side compression of a stored document, applied before it is
+/// written and undone after it is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    /// zstd at its default level.
+    Zstd,
+    /// LZ4 block format with the uncompressed size prepended.
+    Lz4,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 3] = [Compression::None, Compression::Zstd, Compression::Lz4];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    pub fn compress(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => Ok(zstd::encode_all(data.as_slice(), 0)?),
+            Compression::Lz4 => Ok(lz4_flex::compress_prepend_size(&data)),
+        }
+    }
+
+    pub fn decompress(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => Ok(zstd::decode_all(data.as_slice())?),
+            Compression::Lz4 => Ok(lz4_flex::decompress_size_prepended(&data)?),
+        }
+    }
+
+    /// `name` with this compression appended unless there is none, like
+    /// `JsonParser::label`.
+    pub fn label(&self, name: &str) -> String {
+        match self {
+            Compression::None => name.to_string(),
+            compression => format!("{}+{}", name, compression.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_generator;
+
+    #[test]
+    fn round_trips() {
+        let document = data_generator::complex_document(&uuid::Uuid::new_v4().to_string(), 7).to_string();
+        for data in [Vec::new(), b"x".to_vec(), document.into_bytes()] {
+            for compression in Compression::ALL {
+                let compressed = compression.compress(data.clone()).unwrap();
+                assert_eq!(compression.decompress(compressed).unwrap(), data, "{}", compression.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn compresses_repetitive_documents() {
+        let data = data_generator::complex_document(&uuid::Uuid::new_v4().to_string(), 7)
+            .to_string()
+            .repeat(10)
+            .into_bytes();
+        for compression in [Compression::Zstd, Compression::Lz4] {
+            let compressed = compression.compress(data.clone()).unwrap();
+            assert!(compressed.len() < data.len() / 2, "{}", compression.as_str());
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_input() {
+        let garbage = b"not compressed at all".to_vec();
+        for compression in [Compression::Zstd, Compression::Lz4] {
+            assert!(compression.decompress(garbage.clone()).is_err(), "{}", compression.as_str());
+        }
+    }
+}
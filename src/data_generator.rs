@@ -1,75 +1,218 @@
-use sqlx::SqlitePool;
-use uuid::Uuid;
+use serde::Serialize;
 use serde_json::json;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::config::WriteConfig;
+use crate::models::{ComplexUser, User, UserProfile};
+use crate::compression::Compression;
+use crate::store::{ComplexColumnStore, DocumentStore, Layout, UserStore, WriteMode};
+
+/// What the `generate` command writes: simple users into a layout, or the
+/// complex profiles as documents into `users_json` (or its compressed
+/// counterpart) or as rows into `complex_column`.
+#[derive(Debug, Clone, Copy)]
+pub enum Dataset {
+    Users(Layout),
+    Complex(Compression),
+    ComplexColumn,
+}
+
+impl FromStr for Dataset {
+    type Err = anyhow::Error;
 
-pub async fn generate_test_data_column(pool: &SqlitePool, count: i32) -> anyhow::Result<()> {
-    for i in 1..=count {
-        let user_id = Uuid::new_v4().to_string();
-        let preferences = json!({
-            "theme": if i % 2 == 0 { "dark" } else { "light" },
-            "language": match i % 3 { 0 => "ja", 1 => "en", _ => "es" },
-            "notifications": if i % 4 == 0 { "true" } else { "false" }
-        });
-        let social_links = json!([
-            format!("https://twitter.com/user{}", i),
-            format!("https://github.com/user{}", i)
-        ]);
-        
-        sqlx::query!(
-            r#"
-            INSERT INTO users_column (id, name, email, age, bio, avatar_url, preferences, social_links)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#,
-            user_id,
-            format!("User {}", i),
-            format!("user{}@example.com", i),
-            20 + (i % 60),
-            format!("Bio for user {}", i),
-            if i % 3 == 0 { Some(format!("https://example.com/avatar{}.jpg", i)) } else { None },
-            preferences.to_string(),
-            social_links.to_string()
-        )
-        .execute(pool)
-        .await?;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "complex" => Ok(Dataset::Complex(Compression::None)),
+            "complex_zstd" => Ok(Dataset::Complex(Compression::Zstd)),
+            "complex_lz4" => Ok(Dataset::Complex(Compression::Lz4)),
+            "complex_column" => Ok(Dataset::ComplexColumn),
+            layout => Ok(Dataset::Users(layout.parse()?)),
+        }
     }
-    Ok(())
 }
 
-pub async fn generate_test_data_json(pool: &SqlitePool, count: i32) -> anyhow::Result<()> {
-    for i in 1..=count {
-        let user_id = Uuid::new_v4().to_string();
-        let user_data = json!({
-            "id": user_id,
-            "name": format!("User {}", i),
-            "email": format!("user{}@example.com", i),
-            "age": 20 + (i % 60),
-            "profile": {
-                "bio": format!("Bio for user {}", i),
-                "avatar_url": if i % 3 == 0 { Some(format!("https://example.com/avatar{}.jpg", i)) } else { None },
-                "preferences": {
-                    "theme": if i % 2 == 0 { "dark" } else { "light" },
-                    "language": match i % 3 { 0 => "ja", 1 => "en", _ => "es" },
-                    "notifications": if i % 4 == 0 { "true" } else { "false" }
-                },
-                "social_links": [
-                    format!("https://twitter.com/user{}", i),
-                    format!("https://github.com/user{}", i)
-                ]
-            },
-            "created_at": "2024-08-30T08:00:00Z"
-        });
-        
-        sqlx::query!(
-            r#"
-            INSERT INTO users_json (id, data)
-            VALUES (?1, ?2)
-            "#,
-            user_id,
-            user_data.to_string()
-        )
-        .execute(pool)
-        .await?;
+/// Write throughput of one `generate_*` call. Only time spent in the
+/// database is counted, not building the rows.
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteStats {
+    pub rows: i32,
+    pub batch_size: usize,
+    pub transaction_size: usize,
+    pub duration_ms: u128,
+    pub rows_per_sec: f64,
+}
+
+impl WriteStats {
+    fn new(rows: i32, write: &WriteConfig, duration: Duration) -> Self {
+        let secs = duration.as_secs_f64();
+        Self {
+            rows,
+            batch_size: write.batch_size,
+            transaction_size: write.transaction_size,
+            duration_ms: duration.as_millis(),
+            rows_per_sec: if secs > 0.0 { rows as f64 / secs } else { 0.0 },
+        }
+    }
+}
+
+/// Row numbers `1..=count` split into one range per transaction.
+fn transactions(count: i32, transaction_size: usize) -> impl Iterator<Item = RangeInclusive<i32>> {
+    let size = i32::try_from(transaction_size).unwrap_or(i32::MAX);
+    (1..=count)
+        .step_by(transaction_size)
+        .map(move |first| first..=count.min(first.saturating_add(size - 1)))
+}
+
+/// Inserts `count` simple users into `store`.
+pub async fn generate_users(store: &dyn UserStore, count: i32, write: &WriteConfig) -> anyhow::Result<WriteStats> {
+    let mut elapsed = Duration::ZERO;
+    for rows in transactions(count, write.transaction_size) {
+        let users: Vec<User> = rows.map(simple_user).collect();
+        let start = Instant::now();
+        store.insert_rows(&users, WriteMode::Batched, write.batch_size).await?;
+        elapsed += start.elapsed();
+    }
+    Ok(WriteStats::new(count, write, elapsed))
+}
+
+/// Inserts `count` complex profile documents into `store`.
+pub async fn generate_complex(store: &dyn DocumentStore, count: i32, write: &WriteConfig) -> anyhow::Result<WriteStats> {
+    let mut elapsed = Duration::ZERO;
+    for rows in transactions(count, write.transaction_size) {
+        let documents: Vec<(String, serde_json::Value)> = rows
+            .map(|i| {
+                let user_id = Uuid::new_v4().to_string();
+                let document = complex_document(&user_id, i);
+                (user_id, document)
+            })
+            .collect();
+        let start = Instant::now();
+        store.insert_documents(&documents, write.batch_size).await?;
+        elapsed += start.elapsed();
+    }
+    Ok(WriteStats::new(count, write, elapsed))
+}
+
+/// Inserts `count` complex profiles into the `complex_column` tables, with
+/// the same values `generate_complex` writes as documents.
+pub async fn generate_complex_column(
+    store: &ComplexColumnStore,
+    count: i32,
+    write: &WriteConfig,
+) -> anyhow::Result<WriteStats> {
+    let mut elapsed = Duration::ZERO;
+    for rows in transactions(count, write.transaction_size) {
+        let users = rows.map(complex_user).collect::<anyhow::Result<Vec<_>>>()?;
+        let start = Instant::now();
+        store.insert_users(&users, write.batch_size).await?;
+        elapsed += start.elapsed();
+    }
+    Ok(WriteStats::new(count, write, elapsed))
+}
+
+/// Deterministic simple user for row `i`, stored identically by every layout.
+pub fn simple_user(i: i32) -> User {
+    let preferences = HashMap::from([
+        ("theme".to_string(), if i % 2 == 0 { "dark" } else { "light" }.to_string()),
+        ("language".to_string(), match i % 3 { 0 => "ja", 1 => "en", _ => "es" }.to_string()),
+        ("notifications".to_string(), if i % 4 == 0 { "true" } else { "false" }.to_string()),
+    ]);
+
+    User {
+        id: Uuid::new_v4(),
+        name: format!("User {}", i),
+        email: format!("user{}@example.com", i),
+        age: 20 + (i % 60),
+        profile: UserProfile {
+            bio: format!("Bio for user {}", i),
+            avatar_url: if i % 3 == 0 { Some(format!("https://example.com/avatar{}.jpg", i)) } else { None },
+            preferences,
+            social_links: vec![
+                format!("https://twitter.com/user{}", i),
+                format!("https://github.com/user{}", i),
+            ],
+        },
+        created_at: OffsetDateTime::now_utc(),
     }
-    Ok(())
-}
\ No newline at end of file
+}
+
+/// `complex_document` for row `i` as a typed `ComplexUser`.
+pub fn complex_user(i: i32) -> anyhow::Result<ComplexUser> {
+    let user_id = Uuid::new_v4().to_string();
+    Ok(serde_json::from_value(complex_document(&user_id, i))?)
+}
+
+/// Complex profile document for row `i` (achievements, statistics, metadata).
+pub fn complex_document(user_id: &str, i: i32) -> serde_json::Value {
+    json!({
+        "id": user_id,
+        "name": format!("Complex User {}", i),
+        "email": format!("complex.user{}@example.com", i),
+        "age": 20 + (i % 60),
+        "profile": {
+            "bio": format!("Complex bio for user {} with very long description that includes multiple sentences and various details about their background, interests, and activities.", i),
+            "avatar_url": if i % 3 == 0 { Some(format!("https://example.com/avatar{}.jpg", i)) } else { None },
+            "preferences": {
+                "theme": if i % 2 == 0 { "dark" } else { "light" },
+                "language": match i % 3 { 0 => "ja", 1 => "en", _ => "es" },
+                "notifications": if i % 4 == 0 { "true" } else { "false" },
+                "timezone": "Asia/Tokyo",
+                "currency": "JPY",
+                "date_format": "YYYY-MM-DD",
+                "time_format": "24h",
+                "accessibility": {
+                    "high_contrast": i % 2 == 0,
+                    "screen_reader": i % 3 == 0,
+                    "font_size": "medium"
+                }
+            },
+            "social_links": [
+                format!("https://twitter.com/complex_user{}", i),
+                format!("https://github.com/complex_user{}", i),
+                format!("https://linkedin.com/in/complex_user{}", i),
+                format!("https://facebook.com/complex_user{}", i)
+            ],
+            "achievements": [
+                {
+                    "id": format!("achievement_{}", i),
+                    "name": format!("Achievement {}", i),
+                    "description": format!("Description for achievement {}", i),
+                    "earned_at": "2024-08-30T08:00:00Z",
+                    "points": 100 + (i * 10)
+                },
+                {
+                    "id": format!("achievement_{}_2", i),
+                    "name": format!("Special Achievement {}", i),
+                    "description": format!("Special description for achievement {}", i),
+                    "earned_at": "2024-08-30T09:00:00Z",
+                    "points": 200 + (i * 15)
+                }
+            ],
+            "statistics": {
+                "posts_count": 100 + (i * 5),
+                "followers_count": 500 + (i * 20),
+                "following_count": 200 + (i * 10),
+                "likes_received": 1000 + (i * 50),
+                "comments_made": 50 + (i * 3)
+            }
+        },
+        "metadata": {
+            "created_at": "2024-08-30T08:00:00Z",
+            "last_login": "2024-08-30T21:00:00Z",
+            "login_count": 100 + i,
+            "is_verified": i % 5 == 0,
+            "is_premium": i % 7 == 0,
+            "tags": [
+                format!("tag_{}", i),
+                format!("category_{}", i % 10),
+                if i % 2 == 0 { "active" } else { "inactive" },
+                if i % 3 == 0 { "verified" } else { "unverified" }
+            ]
+        }
+    })
+}
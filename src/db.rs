@@ -0,0 +1,257 @@
+use serde::Serialize;
+use sqlx::database::HasArguments;
+use sqlx::encode::IsNull;
+use sqlx::mysql::{MySql, MySqlConnectOptions, MySqlPool};
+use sqlx::pool::PoolOptions;
+use sqlx::postgres::{PgConnectOptions, PgPool, Postgres};
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteConnectOptions, SqlitePool, SqliteTypeInfo};
+use sqlx::{Encode, Type};
+use std::borrow::Cow;
+use std::str::FromStr;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::config::PoolConfig;
+
+/// Database engine a `Db` is connected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    MySql,
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backend::MySql => "mysql",
+            Backend::Sqlite => "sqlite",
+            Backend::Postgres => "postgres",
+        }
+    }
+
+    /// Rewrites `?` placeholders into the backend's native syntax.
+    ///
+    /// Queries are written once with `?`; Postgres needs `$1, $2, ...`.
+    pub fn sql<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        match self {
+            Backend::Postgres => {
+                let mut rewritten = String::with_capacity(query.len() + 8);
+                let mut index = 0;
+                for c in query.chars() {
+                    if c == '?' {
+                        index += 1;
+                        rewritten.push('$');
+                        rewritten.push_str(&index.to_string());
+                    } else {
+                        rewritten.push(c);
+                    }
+                }
+                Cow::Owned(rewritten)
+            }
+            Backend::MySql | Backend::Sqlite => Cow::Borrowed(query),
+        }
+    }
+
+    /// Placeholder for a JSON document bound as text into a column of
+    /// `json_type` (Postgres will not implicitly convert text to JSON).
+    pub fn json_param(&self, json_type: &str) -> Cow<'static, str> {
+        match self {
+            Backend::Postgres => Cow::Owned(format!("CAST(? AS {})", json_type)),
+            Backend::MySql | Backend::Sqlite => Cow::Borrowed("?"),
+        }
+    }
+
+    /// Placeholder compared against a `CHAR(36)` id column. Postgres compares
+    /// a text parameter as `id::text`, which cannot use the primary key.
+    pub fn id_param(&self) -> &'static str {
+        match self {
+            Backend::Postgres => "CAST(? AS CHAR(36))",
+            Backend::MySql | Backend::Sqlite => "?",
+        }
+    }
+
+    /// `expr` cast to a signed 64-bit integer, e.g. to decode an aggregate
+    /// key as `i64` on every backend.
+    pub fn bigint(&self, expr: &str) -> String {
+        match self {
+            Backend::MySql => format!("CAST({} AS SIGNED)", expr),
+            Backend::Sqlite | Backend::Postgres => format!("CAST({} AS BIGINT)", expr),
+        }
+    }
+
+    /// `expr` cast to a double, e.g. to decode a computed ratio as `f64`
+    /// on every backend.
+    pub fn double(&self, expr: &str) -> String {
+        match self {
+            Backend::MySql => format!("CAST({} AS DOUBLE)", expr),
+            Backend::Sqlite => format!("CAST({} AS REAL)", expr),
+            Backend::Postgres => format!("CAST({} AS DOUBLE PRECISION)", expr),
+        }
+    }
+
+    /// Size of `expr` in bytes: the stored, possibly compressed, size on
+    /// Postgres and the encoded length elsewhere.
+    pub fn byte_length(&self, expr: &str) -> String {
+        match self {
+            Backend::MySql => format!("LENGTH({})", expr),
+            Backend::Sqlite => format!("LENGTH(CAST({} AS BLOB))", expr),
+            Backend::Postgres => format!("pg_column_size({})", expr),
+        }
+    }
+
+    /// Select expression returning a JSON column as text under its own name.
+    pub fn json_text(&self, column: &str) -> String {
+        match self {
+            Backend::Postgres => format!("{}::text AS {}", column, column),
+            Backend::MySql | Backend::Sqlite => column.to_string(),
+        }
+    }
+}
+
+/// Timestamp to bind into a `created_at`-style column.
+///
+/// SQLite stores timestamps as text and compares them character by
+/// character, so every value must have the same width for `ORDER BY` to be
+/// chronological. sqlx drops trailing zeros from the fraction, and
+/// `datetime('now')` has none; on SQLite this binds the UTC time with all
+/// nine fraction digits instead, the format of the migrations' defaults.
+/// The other backends bind the `OffsetDateTime` into a real timestamp type.
+#[derive(Debug, Clone, Copy)]
+pub struct Timestamp(pub OffsetDateTime);
+
+const SQLITE_TIMESTAMP: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:9]Z");
+
+impl Type<Sqlite> for Timestamp {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Timestamp {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        let utc = self.0.to_offset(UtcOffset::UTC);
+        Encode::<Sqlite>::encode(utc.format(SQLITE_TIMESTAMP).expect("formattable timestamp"), buf)
+    }
+}
+
+macro_rules! delegate_timestamp {
+    ($db:ty) => {
+        impl Type<$db> for Timestamp {
+            fn type_info() -> <$db as sqlx::Database>::TypeInfo {
+                <OffsetDateTime as Type<$db>>::type_info()
+            }
+
+            fn compatible(ty: &<$db as sqlx::Database>::TypeInfo) -> bool {
+                <OffsetDateTime as Type<$db>>::compatible(ty)
+            }
+        }
+
+        impl<'q> Encode<'q, $db> for Timestamp {
+            fn encode_by_ref(&self, buf: &mut <$db as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+                Encode::<$db>::encode_by_ref(&self.0, buf)
+            }
+        }
+    };
+}
+delegate_timestamp!(MySql);
+delegate_timestamp!(Postgres);
+
+/// Connection pool for whichever backend was selected at startup.
+#[derive(Clone)]
+pub enum Db {
+    MySql(MySqlPool),
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+}
+
+/// Runs `$body` with `$pool` bound to the concrete pool of `$db`.
+///
+/// The body is expanded once per backend, so plain `sqlx::query` calls and
+/// `Row::get` type-check against each driver without a generic wrapper.
+macro_rules! with_pool {
+    ($db:expr, $pool:ident => $body:expr) => {
+        match $db {
+            $crate::db::Db::MySql($pool) => $body,
+            $crate::db::Db::Sqlite($pool) => $body,
+            $crate::db::Db::Postgres($pool) => $body,
+        }
+    };
+}
+pub(crate) use with_pool;
+
+impl Db {
+    /// Connects based on the URL scheme (`mysql://`, `mariadb://`, `sqlite:`
+    /// or `postgres://`), sizing the pool from `pool`.
+    pub async fn connect(url: &str, pool: &PoolConfig) -> anyhow::Result<Self> {
+        if url.starts_with("mysql:") || url.starts_with("mariadb:") {
+            let url = url.replacen("mariadb:", "mysql:", 1);
+            let options = MySqlConnectOptions::from_str(&url)?
+                .statement_cache_capacity(pool.statement_cache_size);
+            let pool = pool_options(pool).connect_with(options).await?;
+            Ok(Db::MySql(pool))
+        } else if url.starts_with("sqlite:") {
+            let options = SqliteConnectOptions::from_str(url)?
+                .create_if_missing(true)
+                .statement_cache_capacity(pool.statement_cache_size);
+            // An in-memory database lives only as long as its connections, so
+            // keep exactly one open for the lifetime of the pool
+            let pool_options = if url.contains(":memory:") || url.contains("mode=memory") {
+                pool_options(pool)
+                    .max_connections(1)
+                    .min_connections(1)
+                    .idle_timeout(None)
+                    .max_lifetime(None)
+            } else {
+                pool_options(pool)
+            };
+            let pool = pool_options.connect_with(options).await?;
+            Ok(Db::Sqlite(pool))
+        } else if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+            let options = PgConnectOptions::from_str(url)?
+                .statement_cache_capacity(pool.statement_cache_size);
+            let pool = pool_options(pool).connect_with(options).await?;
+            Ok(Db::Postgres(pool))
+        } else {
+            anyhow::bail!("unsupported database url: {}", url)
+        }
+    }
+
+    /// A pool of one connection to the same server on MySQL/MariaDB, so that
+    /// `SHOW SESSION STATUS` on it covers everything run through it. The
+    /// connection is never recycled, which would reset the counters. Other
+    /// backends have no session counters and share `self`.
+    pub async fn single_session(&self) -> anyhow::Result<Self> {
+        match self {
+            Db::MySql(pool) => {
+                let pool = PoolOptions::new()
+                    .max_connections(1)
+                    .min_connections(1)
+                    .idle_timeout(None)
+                    .max_lifetime(None)
+                    .connect_with(pool.connect_options().clone())
+                    .await?;
+                Ok(Db::MySql(pool))
+            }
+            Db::Sqlite(_) | Db::Postgres(_) => Ok(self.clone()),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            Db::MySql(_) => Backend::MySql,
+            Db::Sqlite(_) => Backend::Sqlite,
+            Db::Postgres(_) => Backend::Postgres,
+        }
+    }
+}
+
+fn pool_options<DB: sqlx::Database>(config: &PoolConfig) -> PoolOptions<DB> {
+    PoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout())
+}
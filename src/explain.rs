@@ -0,0 +1,175 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::database::HasArguments;
+use sqlx::mysql::{MySql, MySqlArguments};
+use sqlx::postgres::{PgArguments, Postgres};
+use sqlx::query::Query;
+use sqlx::sqlite::{Sqlite, SqliteArguments};
+use sqlx::{Execute, Executor, IntoArguments};
+use std::cell::RefCell;
+use std::future::Future;
+
+use crate::db::Db;
+use crate::migrate::MigrationSet;
+
+/// Whether a benchmark also reports the plans of the statements it ran,
+/// taken from `?explain=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplainMode {
+    #[default]
+    Off,
+    /// The optimizer's estimated plan.
+    Plan,
+    /// The plan with actual row counts and timings, which executes the
+    /// statement once more. Writes only get their estimated plan.
+    Analyze,
+}
+
+/// Plan of one distinct statement a benchmark executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub sql: String,
+    /// Whether `plan` holds actual figures rather than estimates.
+    pub analyzed: bool,
+    /// JSON plan on MySQL/MariaDB and PostgreSQL, the `EXPLAIN ANALYZE`
+    /// tree as a string on MySQL and the `EXPLAIN QUERY PLAN` rows on SQLite.
+    pub plan: Value,
+}
+
+struct Capture {
+    mode: ExplainMode,
+    server: MigrationSet,
+    plans: RefCell<Vec<QueryPlan>>,
+}
+
+tokio::task_local! {
+    static CAPTURE: Capture;
+}
+
+impl Capture {
+    /// `EXPLAIN` form of `sql`, and whether it analyzes.
+    fn explain_sql(&self, sql: &str) -> (String, bool) {
+        // ANALYZE executes the statement, which would apply every write twice
+        let analyze = self.mode == ExplainMode::Analyze && is_select(sql);
+        let prefix = match (self.server, analyze) {
+            (MigrationSet::MariaDb | MigrationSet::MySql, false) => "EXPLAIN FORMAT=JSON",
+            (MigrationSet::MariaDb, true) => "ANALYZE FORMAT=JSON",
+            (MigrationSet::MySql, true) => "EXPLAIN ANALYZE",
+            (MigrationSet::Sqlite, _) => "EXPLAIN QUERY PLAN",
+            (MigrationSet::Postgres, false) => "EXPLAIN (FORMAT JSON)",
+            (MigrationSet::Postgres, true) => "EXPLAIN (ANALYZE, FORMAT JSON)",
+        };
+        (format!("{} {}", prefix, sql), analyze && self.server != MigrationSet::Sqlite)
+    }
+}
+
+fn is_select(sql: &str) -> bool {
+    let sql = sql.trim_start();
+    ["SELECT", "WITH"]
+        .iter()
+        .any(|keyword| sql.get(..keyword.len()).is_some_and(|start| start.eq_ignore_ascii_case(keyword)))
+}
+
+/// Awaits `run` with plan capture on and returns the plans of the distinct
+/// statements it executed through `record`, in order of first execution.
+pub async fn capture<T>(
+    db: &Db,
+    mode: ExplainMode,
+    run: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<Vec<QueryPlan>> {
+    let capture = Capture {
+        mode,
+        server: MigrationSet::detect(db).await?,
+        plans: RefCell::default(),
+    };
+    CAPTURE
+        .scope(capture, async {
+            run.await?;
+            Ok(CAPTURE.with(|capture| capture.plans.take()))
+        })
+        .await
+}
+
+/// Explains the statement `query` builds, with its arguments, if a
+/// `capture` is running and has not seen the statement yet. The caller
+/// then builds it again to execute it, as arguments cannot be cloned on
+/// every backend.
+pub async fn record<'q, 'c, DB, A, E>(executor: E, query: impl FnOnce() -> Query<'q, DB, A>) -> anyhow::Result<()>
+where
+    DB: ExplainDatabase,
+    A: 'q + IntoArguments<'q, DB>,
+    E: Executor<'c, Database = DB>,
+{
+    if CAPTURE.try_with(|_| ()).is_err() {
+        return Ok(());
+    }
+
+    let mut query = query();
+    let sql = query.sql().to_string();
+    let explain = CAPTURE.with(|capture| {
+        let seen = capture.plans.borrow().iter().any(|plan| plan.sql == sql);
+        (!seen).then(|| capture.explain_sql(&sql))
+    });
+    let Some((explain_sql, analyzed)) = explain else {
+        return Ok(());
+    };
+
+    let arguments = query.take_arguments().unwrap_or_default();
+    let plan = DB::explain(&explain_sql, arguments, executor).await?;
+    CAPTURE.with(|capture| capture.plans.borrow_mut().push(QueryPlan { sql, analyzed, plan }));
+    Ok(())
+}
+
+/// Runs an `EXPLAIN` statement and turns its output into JSON.
+pub trait ExplainDatabase: sqlx::Database {
+    fn explain<'e, 'q: 'e, 'c: 'e, E>(
+        sql: &'e str,
+        arguments: <Self as HasArguments<'q>>::Arguments,
+        executor: E,
+    ) -> BoxFuture<'e, anyhow::Result<Value>>
+    where
+        E: 'e + Executor<'c, Database = Self>;
+}
+
+impl ExplainDatabase for MySql {
+    fn explain<'e, 'q: 'e, 'c: 'e, E>(sql: &'e str, arguments: MySqlArguments, executor: E) -> BoxFuture<'e, anyhow::Result<Value>>
+    where
+        E: 'e + Executor<'c, Database = Self>,
+    {
+        Box::pin(async move {
+            let plan: String = sqlx::query_scalar_with(sql, arguments).fetch_one(executor).await?;
+            Ok(serde_json::from_str(&plan).unwrap_or(Value::String(plan)))
+        })
+    }
+}
+
+impl ExplainDatabase for Sqlite {
+    fn explain<'e, 'q: 'e, 'c: 'e, E>(
+        sql: &'e str,
+        arguments: SqliteArguments<'q>,
+        executor: E,
+    ) -> BoxFuture<'e, anyhow::Result<Value>>
+    where
+        E: 'e + Executor<'c, Database = Self>,
+    {
+        let arguments: SqliteArguments<'e> = arguments;
+        Box::pin(async move {
+            let rows: Vec<(i64, i64, i64, String)> = sqlx::query_as_with(sql, arguments).fetch_all(executor).await?;
+            Ok(rows
+                .into_iter()
+                .map(|(id, parent, _, detail)| serde_json::json!({ "id": id, "parent": parent, "detail": detail }))
+                .collect())
+        })
+    }
+}
+
+impl ExplainDatabase for Postgres {
+    fn explain<'e, 'q: 'e, 'c: 'e, E>(sql: &'e str, arguments: PgArguments, executor: E) -> BoxFuture<'e, anyhow::Result<Value>>
+    where
+        E: 'e + Executor<'c, Database = Self>,
+    {
+        Box::pin(async move { Ok(sqlx::query_scalar_with(sql, arguments).fetch_one(executor).await?) })
+    }
+}
@@ -0,0 +1,17 @@
+//! Storage layouts, benchmarks and commands of the server, shared by the
+//! binary and the integration tests.
+
+pub mod benchmark;
+pub mod cache;
+pub mod commands;
+pub mod compression;
+pub mod config;
+pub mod data_generator;
+pub mod db;
+pub mod explain;
+pub mod memory;
+pub mod migrate;
+pub mod models;
+pub mod parser;
+pub mod stats;
+pub mod store;
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+
+/// Memory the measured runs of a benchmark used. Both sources cover the
+/// whole process, so concurrent requests are counted too.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Largest growth of the resident set over any measured run, from the
+    /// kernel's high-water mark (Linux only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+    /// Bytes allocated per measured run (mean), counting every `realloc` as
+    /// a new allocation of its full size. Needs the `alloc-counter` feature,
+    /// as do the other heap figures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_bytes: Option<u64>,
+    /// Allocations per measured run (mean).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<u64>,
+    /// Largest growth of the live heap over any measured run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_live_bytes: Option<u64>,
+}
+
+impl MemoryStats {
+    /// Combines the measured runs, or `None` if nothing could be measured.
+    pub fn new(runs: &[RunMemory]) -> Option<Self> {
+        let peak_rss_bytes = runs.iter().filter_map(|run| run.peak_rss).max();
+        let heap: Vec<Allocations> = runs.iter().filter_map(|run| run.heap).collect();
+        if heap.is_empty() {
+            return peak_rss_bytes.map(|peak_rss_bytes| Self {
+                peak_rss_bytes: Some(peak_rss_bytes),
+                ..Self::default()
+            });
+        }
+
+        let mean = |field: fn(&Allocations) -> u64| heap.iter().map(field).sum::<u64>() / heap.len() as u64;
+        Some(Self {
+            peak_rss_bytes,
+            allocated_bytes: Some(mean(|heap| heap.bytes)),
+            allocations: Some(mean(|heap| heap.count)),
+            peak_live_bytes: heap.iter().map(|heap| heap.peak_live).max(),
+        })
+    }
+}
+
+/// What one measured run used, between `RunStart::begin` and `finish`.
+pub struct RunMemory {
+    peak_rss: Option<u64>,
+    heap: Option<Allocations>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Allocations {
+    bytes: u64,
+    count: u64,
+    peak_live: u64,
+}
+
+/// Baselines taken right before a measured run.
+pub struct RunStart {
+    rss: Option<u64>,
+    #[cfg(feature = "alloc-counter")]
+    heap: counter::Snapshot,
+}
+
+impl RunStart {
+    /// Resets the high-water marks; call it last before the run.
+    pub fn begin() -> Self {
+        Self {
+            rss: reset_peak_rss(),
+            #[cfg(feature = "alloc-counter")]
+            heap: counter::reset_peak(),
+        }
+    }
+
+    pub fn finish(self) -> RunMemory {
+        #[cfg(feature = "alloc-counter")]
+        let heap = Some(counter::since(&self.heap));
+        #[cfg(not(feature = "alloc-counter"))]
+        let heap = None;
+
+        RunMemory {
+            peak_rss: self.rss.zip(peak_rss()).map(|(baseline, peak)| peak.saturating_sub(baseline)),
+            heap,
+        }
+    }
+}
+
+/// Returns freed heap memory to the kernel and resets the resident set
+/// high-water mark to the current resident set, which it returns in bytes.
+/// `None` where the kernel does not expose it (anything but Linux).
+fn reset_peak_rss() -> Option<u64> {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    // SAFETY: malloc_trim only releases free memory of the allocator
+    unsafe {
+        libc::malloc_trim(0);
+    }
+    // "5" resets VmHWM (Linux 4.0+)
+    std::fs::write("/proc/self/clear_refs", "5").ok()?;
+    status_bytes("VmRSS:")
+}
+
+/// Resident set high-water mark since the last `reset_peak_rss`, in bytes.
+fn peak_rss() -> Option<u64> {
+    status_bytes("VmHWM:")
+}
+
+/// Value of a `/proc/self/status` field given in kB.
+fn status_bytes(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with(field))?;
+    let kb: u64 = line[field.len()..].trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(feature = "alloc-counter")]
+#[global_allocator]
+static ALLOCATOR: counter::CountingAllocator = counter::CountingAllocator;
+
+/// Global allocator that counts what passes through to the system allocator.
+#[cfg(feature = "alloc-counter")]
+mod counter {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::Allocations;
+
+    static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static LIVE: AtomicU64 = AtomicU64::new(0);
+    static PEAK_LIVE: AtomicU64 = AtomicU64::new(0);
+
+    pub struct CountingAllocator;
+
+    /// Counts an allocation of `size` bytes that replaced `freed` bytes.
+    fn allocated(size: usize, freed: usize) {
+        ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        if size >= freed {
+            let grown = (size - freed) as u64;
+            let live = LIVE.fetch_add(grown, Ordering::Relaxed) + grown;
+            PEAK_LIVE.fetch_max(live, Ordering::Relaxed);
+        } else {
+            LIVE.fetch_sub((freed - size) as u64, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                allocated(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                allocated(layout.size(), 0);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            LIVE.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                allocated(new_size, layout.size());
+            }
+            new_ptr
+        }
+    }
+
+    /// Counters at the start of a run.
+    pub struct Snapshot {
+        allocated: u64,
+        allocations: u64,
+        live: u64,
+    }
+
+    /// Resets the peak to the current live bytes and takes a snapshot.
+    pub fn reset_peak() -> Snapshot {
+        let live = LIVE.load(Ordering::Relaxed);
+        PEAK_LIVE.store(live, Ordering::Relaxed);
+        Snapshot {
+            allocated: ALLOCATED.load(Ordering::Relaxed),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            live,
+        }
+    }
+
+    /// Allocations since `start`.
+    pub fn since(start: &Snapshot) -> Allocations {
+        Allocations {
+            bytes: ALLOCATED.load(Ordering::Relaxed) - start.allocated,
+            count: ALLOCATIONS.load(Ordering::Relaxed) - start.allocations,
+            peak_live: PEAK_LIVE.load(Ordering::Relaxed).saturating_sub(start.live),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::Row;
+use std::collections::HashSet;
+
+use crate::db::{with_pool, Db};
+use crate::store::Layout;
+
+static MARIADB: Migrator = sqlx::migrate!("./migrations/mariadb");
+static MYSQL: Migrator = sqlx::migrate!("./migrations/mysql");
+static SQLITE: Migrator = sqlx::migrate!("./migrations/sqlite");
+static POSTGRES: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Indexes the benchmarks are designed around. A migration set may leave
+/// some out when the server cannot build them (see `skip_reason`).
+const INDEXES: &[(Layout, &str)] = &[
+    (Layout::Column, "idx_users_column_created_at"),
+    (Layout::Column, "idx_users_column_email"),
+    (Layout::Column, "idx_users_column_age"),
+    (Layout::Json, "idx_users_json_created_at"),
+    (Layout::Json, "idx_users_json_email"),
+    (Layout::Json, "idx_users_json_age"),
+    (Layout::Jsonb, "idx_users_jsonb_created_at"),
+    (Layout::Jsonb, "idx_users_jsonb_email"),
+    (Layout::Jsonb, "idx_users_jsonb_age"),
+    (Layout::JsonbGin, "idx_users_jsonb_gin_created_at"),
+    (Layout::JsonbGin, "idx_users_jsonb_gin_data"),
+    (Layout::Hybrid, "idx_users_hybrid_created_at"),
+    (Layout::Hybrid, "idx_users_hybrid_email"),
+    (Layout::Hybrid, "idx_users_hybrid_age"),
+    (Layout::Normalized, "idx_users_normalized_created_at"),
+    (Layout::Normalized, "idx_users_normalized_email"),
+    (Layout::Normalized, "idx_users_normalized_age"),
+];
+
+/// Which directory under `migrations/` applies to the connected server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationSet {
+    MariaDb,
+    MySql,
+    Sqlite,
+    Postgres,
+}
+
+impl MigrationSet {
+    /// MariaDB and MySQL share a driver, so tell them apart by server version.
+    pub async fn detect(db: &Db) -> anyhow::Result<Self> {
+        Ok(match db {
+            Db::MySql(pool) => {
+                let version: String = sqlx::query_scalar("SELECT VERSION()")
+                    .fetch_one(pool)
+                    .await?;
+                if version.contains("MariaDB") {
+                    MigrationSet::MariaDb
+                } else {
+                    MigrationSet::MySql
+                }
+            }
+            Db::Sqlite(_) => MigrationSet::Sqlite,
+            Db::Postgres(_) => MigrationSet::Postgres,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationSet::MariaDb => "mariadb",
+            MigrationSet::MySql => "mysql",
+            MigrationSet::Sqlite => "sqlite",
+            MigrationSet::Postgres => "postgres",
+        }
+    }
+
+    fn migrator(&self) -> &'static Migrator {
+        match self {
+            MigrationSet::MariaDb => &MARIADB,
+            MigrationSet::MySql => &MYSQL,
+            MigrationSet::Sqlite => &SQLITE,
+            MigrationSet::Postgres => &POSTGRES,
+        }
+    }
+
+    fn skip_reason(&self, index: &str) -> Option<&'static str> {
+        match (self, index) {
+            (MigrationSet::MariaDb, "idx_users_json_email" | "idx_users_json_age") => {
+                Some("JSON functional indexes are not supported on MariaDB 10.11")
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum IndexStatus {
+    Created,
+    Existing,
+    Skipped(&'static str),
+    Missing,
+}
+
+#[derive(Debug)]
+pub struct IndexReport {
+    pub table: &'static str,
+    pub index: &'static str,
+    pub status: IndexStatus,
+}
+
+#[derive(Debug)]
+pub struct MigrationReport {
+    pub set: MigrationSet,
+    pub applied: Vec<String>,
+    pub indexes: Vec<IndexReport>,
+}
+
+impl MigrationReport {
+    pub fn print(&self) {
+        println!("🗂️ Migration set: {}", self.set.as_str());
+        if self.applied.is_empty() {
+            println!("  No pending migrations");
+        }
+        for migration in &self.applied {
+            println!("  Applied {}", migration);
+        }
+        for index in &self.indexes {
+            match &index.status {
+                IndexStatus::Created => println!("  ✅ {}.{} created", index.table, index.index),
+                IndexStatus::Existing => println!("  ✅ {}.{} already present", index.table, index.index),
+                IndexStatus::Skipped(reason) => {
+                    println!("  ⏭️ {}.{} skipped: {}", index.table, index.index, reason)
+                }
+                IndexStatus::Missing => println!("  ⚠️ {}.{} missing", index.table, index.index),
+            }
+        }
+    }
+}
+
+/// Applies the pending migrations for the connected server and reports which
+/// of the expected indexes exist afterwards.
+pub async fn run(db: &Db) -> anyhow::Result<MigrationReport> {
+    let set = MigrationSet::detect(db).await?;
+    let migrator = set.migrator();
+
+    let before = list_indexes(db).await?;
+    let applied_before: HashSet<i64> = applied_versions(db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    with_pool!(db, pool => migrator.run(pool).await?);
+
+    let after = list_indexes(db).await?;
+    let backend = db.backend();
+
+    let applied = migrator
+        .iter()
+        .filter(|migration| !applied_before.contains(&migration.version))
+        .map(|migration| format!("{} {}", migration.version, migration.description))
+        .collect();
+
+    let indexes = INDEXES
+        .iter()
+        .filter(|(layout, _)| layout.is_supported_by(backend))
+        .map(|&(layout, index)| {
+            let status = if before.contains(index) {
+                IndexStatus::Existing
+            } else if after.contains(index) {
+                IndexStatus::Created
+            } else if let Some(reason) = set.skip_reason(index) {
+                IndexStatus::Skipped(reason)
+            } else {
+                IndexStatus::Missing
+            };
+            IndexReport {
+                table: layout.table(),
+                index,
+                status,
+            }
+        })
+        .collect();
+
+    Ok(MigrationReport {
+        set,
+        applied,
+        indexes,
+    })
+}
+
+/// Fails unless every migration in the server's set has been applied
+/// unchanged, so benchmarks never run against a stale schema.
+pub async fn ensure_up_to_date(db: &Db) -> anyhow::Result<()> {
+    let set = MigrationSet::detect(db).await?;
+    let applied = applied_versions_with_checksums(db).await.map_err(|e| {
+        anyhow::anyhow!("cannot read migration history ({}); run with --migrate", e)
+    })?;
+
+    let mut pending = Vec::new();
+    for migration in set.migrator().iter() {
+        match applied.iter().find(|(version, _)| *version == migration.version) {
+            Some((_, checksum)) if checksum.as_slice() != &*migration.checksum => {
+                anyhow::bail!(
+                    "migration {} ({}) was modified after it was applied",
+                    migration.version,
+                    set.as_str()
+                );
+            }
+            Some(_) => {}
+            None => pending.push(format!("{} {}", migration.version, migration.description)),
+        }
+    }
+
+    if !pending.is_empty() {
+        anyhow::bail!(
+            "schema is out of date, pending {} migrations: {}; run with --migrate",
+            set.as_str(),
+            pending.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+async fn applied_versions(db: &Db) -> anyhow::Result<Vec<i64>> {
+    Ok(applied_versions_with_checksums(db)
+        .await?
+        .into_iter()
+        .map(|(version, _)| version)
+        .collect())
+}
+
+async fn applied_versions_with_checksums(db: &Db) -> anyhow::Result<Vec<(i64, Vec<u8>)>> {
+    with_pool!(db, pool => {
+        let mut conn = pool.acquire().await?;
+        if let Some(version) = conn.dirty_version().await? {
+            anyhow::bail!("migration {} failed part way; fix the schema by hand", version);
+        }
+        let applied = conn.list_applied_migrations().await?;
+        Ok(applied
+            .into_iter()
+            .map(|migration| (migration.version, migration.checksum.into_owned()))
+            .collect())
+    })
+}
+
+async fn list_indexes(db: &Db) -> anyhow::Result<HashSet<String>> {
+    let names = match db {
+        Db::MySql(pool) => {
+            sqlx::query(
+                r#"
+                SELECT DISTINCT CAST(INDEX_NAME AS CHAR) AS index_name
+                FROM information_schema.STATISTICS
+                WHERE TABLE_SCHEMA = DATABASE()
+                "#,
+            )
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| row.get("index_name"))
+            .collect()
+        }
+        Db::Sqlite(pool) => {
+            sqlx::query("SELECT name AS index_name FROM sqlite_master WHERE type = 'index'")
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| row.get("index_name"))
+                .collect()
+        }
+        Db::Postgres(pool) => {
+            sqlx::query(
+                "SELECT indexname::text AS index_name FROM pg_indexes WHERE schemaname = current_schema()",
+            )
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| row.get("index_name"))
+            .collect()
+        }
+    };
+
+    Ok(names)
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub age: i32,
+    pub profile: UserProfile,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub bio: String,
+    pub avatar_url: Option<String>,
+    pub preferences: HashMap<String, String>,
+    pub social_links: Vec<String>,
+}
+
+/// Document written by `data_generator::complex_document`. The `Option`
+/// fields are absent in stored documents and filled in by the complex
+/// processing benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplexUser {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub age: i32,
+    pub profile: ComplexProfile,
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplexProfile {
+    pub bio: String,
+    pub avatar_url: Option<String>,
+    pub preferences: ComplexPreferences,
+    pub social_links: Vec<String>,
+    pub achievements: Vec<Achievement>,
+    pub statistics: Statistics,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_achievement_points: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio_analysis: Option<BioAnalysis>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplexPreferences {
+    pub theme: String,
+    pub language: String,
+    pub notifications: String,
+    pub timezone: String,
+    pub currency: String,
+    pub date_format: String,
+    pub time_format: String,
+    pub accessibility: Accessibility,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Accessibility {
+    pub high_contrast: bool,
+    pub screen_reader: bool,
+    pub font_size: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub earned_at: OffsetDateTime,
+    pub points: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statistics {
+    pub posts_count: u64,
+    pub followers_count: u64,
+    pub following_count: u64,
+    pub likes_received: u64,
+    pub comments_made: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engagement_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_login: OffsetDateTime,
+    pub login_count: u64,
+    pub is_verified: bool,
+    pub is_premium: bool,
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_analysis: Option<TagAnalysis>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagAnalysis {
+    pub total_tags: usize,
+    pub verified_tags: usize,
+    pub verification_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BioAnalysis {
+    pub word_count: usize,
+    pub char_count: usize,
+    pub sentence_count: usize,
+    pub avg_words_per_sentence: f64,
+}
@@ -0,0 +1,113 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Library the document layouts parse JSON text with. All of them
+/// deserialize through serde into the same `User`/`Value` types, so only the
+/// parser differs. Variants other than `serde_json` need their cargo feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonParser {
+    #[default]
+    SerdeJson,
+    #[cfg(feature = "simd-json")]
+    SimdJson,
+    #[cfg(feature = "sonic-rs")]
+    SonicRs,
+}
+
+impl JsonParser {
+    /// Every parser compiled into this build.
+    pub const ALL: &'static [JsonParser] = &[
+        JsonParser::SerdeJson,
+        #[cfg(feature = "simd-json")]
+        JsonParser::SimdJson,
+        #[cfg(feature = "sonic-rs")]
+        JsonParser::SonicRs,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JsonParser::SerdeJson => "serde_json",
+            #[cfg(feature = "simd-json")]
+            JsonParser::SimdJson => "simd_json",
+            #[cfg(feature = "sonic-rs")]
+            JsonParser::SonicRs => "sonic_rs",
+        }
+    }
+
+    /// Deserializes `text`, which is taken by value because simd-json parses
+    /// in place.
+    pub fn parse<T: DeserializeOwned>(&self, text: String) -> anyhow::Result<T> {
+        match self {
+            JsonParser::SerdeJson => Ok(serde_json::from_str(&text)?),
+            #[cfg(feature = "simd-json")]
+            JsonParser::SimdJson => Ok(simd_json::serde::from_slice(&mut text.into_bytes())?),
+            #[cfg(feature = "sonic-rs")]
+            JsonParser::SonicRs => Ok(sonic_rs::from_str(&text)?),
+        }
+    }
+
+    /// `name` with this parser appended unless it is the default, so results
+    /// of different parsers get their own report column.
+    pub fn label(&self, name: &str) -> String {
+        match self {
+            JsonParser::SerdeJson => name.to_string(),
+            #[allow(unreachable_patterns)]
+            parser => format!("{}+{}", name, parser.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_generator;
+    use crate::models::{ComplexUser, User};
+    use serde_json::Value;
+
+    /// A simple user and a complex profile as the generators write them,
+    /// plus escapes, non-ASCII text and numbers at the edges.
+    fn documents() -> Vec<String> {
+        let user = serde_json::to_string(&data_generator::simple_user(3)).unwrap();
+        let complex = data_generator::complex_document(&uuid::Uuid::new_v4().to_string(), 3).to_string();
+        let edges = serde_json::json!({
+            "text": "tab\tquote\"backslash\\ unicode é ✓ 🦀",
+            "numbers": [0, -1, i64::MAX, u64::MAX, 0.1, -2.5e-8, 1e300],
+            "nested": { "empty": {}, "list": [], "null": null, "flag": true }
+        })
+        .to_string();
+        vec![user, complex, edges]
+    }
+
+    #[test]
+    fn every_parser_matches_serde_json() {
+        for document in documents() {
+            let expected: Value = serde_json::from_str(&document).unwrap();
+            for parser in JsonParser::ALL {
+                let parsed: Value = parser.parse(document.clone()).unwrap();
+                assert_eq!(parsed, expected, "{}", parser.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn every_parser_reads_the_models() {
+        let [user, complex, _] = <[String; 3]>::try_from(documents()).unwrap();
+        let expected_user: Value = serde_json::from_str(&user).unwrap();
+        let expected_complex: Value = serde_json::from_str(&complex).unwrap();
+        for parser in JsonParser::ALL {
+            let parsed: User = parser.parse(user.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), expected_user, "{}", parser.as_str());
+            let parsed: ComplexUser = parser.parse(complex.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), expected_complex, "{}", parser.as_str());
+        }
+    }
+
+    #[test]
+    fn every_parser_rejects_invalid_json() {
+        for parser in JsonParser::ALL {
+            assert!(parser.parse::<Value>("{\"id\": ".to_string()).is_err(), "{}", parser.as_str());
+            assert!(parser.parse::<User>("{}".to_string()).is_err(), "{}", parser.as_str());
+        }
+    }
+}
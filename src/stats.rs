@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Row};
+use std::collections::BTreeMap;
+
+use crate::db::{with_pool, Backend, Db};
+use crate::store::Layout;
+
+/// Child tables stored next to the main table of `users_normalized`.
+const NORMALIZED_CHILD_TABLES: [&str; 2] = ["user_preferences", "user_social_links"];
+
+/// Tables of the `complex_column` dataset.
+const COMPLEX_COLUMN_TABLES: [&str; 3] = ["complex_column", "complex_achievements", "complex_tags"];
+
+/// `SHOW SESSION STATUS` counters diffed around the measured runs of a
+/// benchmark on MySQL/MariaDB, besides every `Handler_read_*`.
+const SESSION_COUNTERS: [&str; 5] = [
+    "Bytes_sent",
+    "Created_tmp_tables",
+    "Created_tmp_disk_tables",
+    "Sort_merge_passes",
+    "Select_scan",
+];
+
+/// Counters that only exist server-wide, so they include the work of other
+/// sessions. They are reported apart from the session ones.
+const SERVER_COUNTERS: [&str; 3] = [
+    "Innodb_rows_read",
+    "Innodb_buffer_pool_read_requests",
+    "Innodb_buffer_pool_reads",
+];
+
+/// Value of each session status counter, by name.
+pub type SessionCounters = BTreeMap<String, i64>;
+
+/// Change of the status counters over the measured runs of a benchmark.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CounterDeltas {
+    /// Counters of the benchmark's own session.
+    #[serde(default)]
+    pub session: SessionCounters,
+    /// Server-wide counters, which include other sessions' work.
+    #[serde(default)]
+    pub server: SessionCounters,
+}
+
+/// Sums the counter changes of the measured runs only, leaving out warmup,
+/// cache eviction and the statements that read the counters.
+pub struct CounterWindow {
+    /// What two back-to-back snapshots differ by.
+    overhead: SessionCounters,
+    before: SessionCounters,
+    total: SessionCounters,
+}
+
+impl CounterWindow {
+    /// `None` on backends without session counters.
+    pub async fn open(db: &Db) -> anyhow::Result<Option<Self>> {
+        let Some(first) = session_counters(db).await? else {
+            return Ok(None);
+        };
+        let Some(second) = session_counters(db).await? else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new(&first, second)))
+    }
+
+    /// Window from two back-to-back snapshots.
+    fn new(first: &SessionCounters, second: SessionCounters) -> Self {
+        let mut overhead = counter_deltas(first, &second);
+        overhead.retain(|name, _| !is_server_counter(name));
+        Self {
+            overhead,
+            before: second,
+            total: SessionCounters::new(),
+        }
+    }
+
+    /// Call right before a measured run.
+    pub async fn run_starts(&mut self, db: &Db) -> anyhow::Result<()> {
+        if let Some(before) = session_counters(db).await? {
+            self.before = before;
+        }
+        Ok(())
+    }
+
+    /// Call right after a measured run.
+    pub async fn run_ends(&mut self, db: &Db) -> anyhow::Result<()> {
+        if let Some(after) = session_counters(db).await? {
+            self.add_run(&after);
+        }
+        Ok(())
+    }
+
+    /// Adds what changed from `before` to `after`, less the overhead.
+    fn add_run(&mut self, after: &SessionCounters) {
+        for (name, delta) in counter_deltas(&self.before, after) {
+            let overhead = self.overhead.get(&name).copied().unwrap_or(0);
+            *self.total.entry(name).or_insert(0) += delta - overhead;
+        }
+    }
+
+    pub fn close(self) -> CounterDeltas {
+        let (server, session) = self.total.into_iter().partition(|(name, _)| is_server_counter(name));
+        CounterDeltas { session, server }
+    }
+}
+
+fn is_server_counter(name: &str) -> bool {
+    SERVER_COUNTERS.contains(&name)
+}
+
+/// Footprint of one table as the server reports it, in bytes. MySQL/MariaDB
+/// and PostgreSQL estimate `rows` from their statistics; SQLite counts them.
+#[derive(Debug, Serialize)]
+pub struct TableStorage {
+    /// Layout or dataset the table belongs to.
+    pub layout: &'static str,
+    pub table: &'static str,
+    pub rows: i64,
+    pub avg_row_length: i64,
+    pub data_length: i64,
+    pub index_length: i64,
+}
+
+impl TableStorage {
+    pub fn total_length(&self) -> i64 {
+        self.data_length + self.index_length
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageReport {
+    pub backend: &'static str,
+    pub analyzed: bool,
+    pub tables: Vec<TableStorage>,
+}
+
+/// Every benchmarked table available on `backend`, with the layout or
+/// dataset it belongs to.
+fn tables(backend: Backend) -> Vec<(&'static str, &'static str)> {
+    let mut tables = Vec::new();
+    for layout in Layout::ALL.into_iter().filter(|layout| layout.is_supported_by(backend)) {
+        tables.push((layout.as_str(), layout.table()));
+        if layout == Layout::Normalized {
+            tables.extend(NORMALIZED_CHILD_TABLES.map(|table| (layout.as_str(), table)));
+        }
+    }
+    tables.extend(COMPLEX_COLUMN_TABLES.map(|table| ("complex_column", table)));
+    tables
+}
+
+/// Reads data length, index length, row count and average row length of
+/// every benchmarked table: `information_schema.TABLES` on MySQL/MariaDB,
+/// the `dbstat` virtual table on SQLite and `pg_class` with the
+/// `pg_*_size` functions on PostgreSQL. With `analyze`, the statistics are
+/// refreshed first; MySQL 8 otherwise serves `information_schema.TABLES`
+/// from a cache that can be a day old.
+pub async fn storage(db: &Db, analyze: bool) -> anyhow::Result<StorageReport> {
+    let tables = tables(db.backend());
+    if analyze {
+        analyze_tables(db, &tables).await?;
+    }
+
+    let mut report = Vec::with_capacity(tables.len());
+    for (layout, table) in tables {
+        let Some((rows, avg_row_length, data_length, index_length)) = table_storage(db, table).await? else {
+            continue;
+        };
+        report.push(TableStorage {
+            layout,
+            table,
+            rows,
+            avg_row_length: avg_row_length.unwrap_or(if rows > 0 { data_length / rows } else { 0 }),
+            data_length,
+            index_length,
+        });
+    }
+
+    Ok(StorageReport {
+        backend: db.backend().as_str(),
+        analyzed: analyze,
+        tables: report,
+    })
+}
+
+async fn analyze_tables(db: &Db, tables: &[(&'static str, &'static str)]) -> anyhow::Result<()> {
+    let names: Vec<&str> = tables.iter().map(|(_, table)| *table).collect();
+    // Unprepared, since MySQL does not prepare every ANALYZE form
+    let sql = match db.backend() {
+        Backend::MySql => format!("ANALYZE TABLE {}", names.join(", ")),
+        Backend::Sqlite => "ANALYZE".to_string(),
+        Backend::Postgres => format!("ANALYZE {}", names.join(", ")),
+    };
+
+    with_pool!(db, pool => {
+        pool.execute(sql.as_str()).await?;
+    });
+    Ok(())
+}
+
+/// Rows, average row length where the server reports one, data length and
+/// index length of `table`, or `None` if it does not exist.
+async fn table_storage(db: &Db, table: &str) -> anyhow::Result<Option<(i64, Option<i64>, i64, i64)>> {
+    let storage = match db {
+        Db::MySql(pool) => {
+            let row: Option<(i64, i64, i64, i64)> = sqlx::query_as(
+                r#"
+                SELECT CAST(COALESCE(TABLE_ROWS, 0) AS SIGNED),
+                       CAST(COALESCE(AVG_ROW_LENGTH, 0) AS SIGNED),
+                       CAST(COALESCE(DATA_LENGTH, 0) AS SIGNED),
+                       CAST(COALESCE(INDEX_LENGTH, 0) AS SIGNED)
+                FROM information_schema.TABLES
+                WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+                "#,
+            )
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+            row.map(|(rows, avg_row_length, data_length, index_length)| {
+                (rows, Some(avg_row_length), data_length, index_length)
+            })
+        }
+        Db::Sqlite(pool) => {
+            // Pages of the table's b-tree and of every index on it
+            let exists: Option<String> =
+                sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+                    .bind(table)
+                    .fetch_optional(pool)
+                    .await?;
+            if exists.is_none() {
+                return Ok(None);
+            }
+            let sql = format!(
+                r#"
+                SELECT (SELECT COUNT(*) FROM {}),
+                       COALESCE(SUM(CASE WHEN m.type = 'table' THEN d.pgsize END), 0),
+                       COALESCE(SUM(CASE WHEN m.type = 'index' THEN d.pgsize END), 0)
+                FROM sqlite_master m
+                JOIN dbstat d ON d.name = m.name
+                WHERE m.tbl_name = ?
+                "#,
+                table
+            );
+            let (rows, data_length, index_length): (i64, i64, i64) =
+                sqlx::query_as(&sql).bind(table).fetch_one(pool).await?;
+            Some((rows, None, data_length, index_length))
+        }
+        Db::Postgres(pool) => {
+            let row: Option<(i64, i64, i64)> = sqlx::query_as(
+                r#"
+                SELECT CAST(GREATEST(reltuples, 0) AS BIGINT),
+                       pg_table_size(oid),
+                       pg_indexes_size(oid)
+                FROM pg_class
+                WHERE oid = to_regclass($1)
+                "#,
+            )
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+            row.map(|(rows, data_length, index_length)| (rows, None, data_length, index_length))
+        }
+    };
+
+    Ok(storage)
+}
+
+/// Snapshot of the session status counters, or `None` on backends that have
+/// none. `db` must be a single-connection pool (`Db::single_session`) for the
+/// counters to cover everything run through it.
+pub async fn session_counters(db: &Db) -> anyhow::Result<Option<SessionCounters>> {
+    let Db::MySql(pool) = db else {
+        return Ok(None);
+    };
+    let names: Vec<String> = SESSION_COUNTERS
+        .iter()
+        .chain(&SERVER_COUNTERS)
+        .map(|name| format!("'{}'", name))
+        .collect();
+    // Unprepared, since not every MySQL version prepares SHOW STATUS
+    let sql = format!(
+        "SHOW SESSION STATUS WHERE Variable_name LIKE 'Handler_read%' OR Variable_name IN ({})",
+        names.join(", ")
+    );
+
+    let rows = pool.fetch_all(sql.as_str()).await?;
+    let counters = rows
+        .iter()
+        .filter_map(|row| {
+            let value: String = row.get(1);
+            Some((row.get(0), value.parse().ok()?))
+        })
+        .collect();
+    Ok(Some(counters))
+}
+
+/// Change of every counter from `before` to `after`.
+pub fn counter_deltas(before: &SessionCounters, after: &SessionCounters) -> SessionCounters {
+    after
+        .iter()
+        .map(|(name, value)| (name.clone(), value - before.get(name).copied().unwrap_or(0)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(values: &[(&str, i64)]) -> SessionCounters {
+        values.iter().map(|&(name, value)| (name.to_string(), value)).collect()
+    }
+
+    #[test]
+    fn counter_deltas_diff_by_name() {
+        let before = counters(&[("Bytes_sent", 100), ("Select_scan", 4), ("Handler_read_key", 7)]);
+        let after = counters(&[("Bytes_sent", 250), ("Select_scan", 4), ("Handler_read_next", 9)]);
+
+        // Counters missing before count from 0; ones missing after are dropped
+        let expected = counters(&[("Bytes_sent", 150), ("Select_scan", 0), ("Handler_read_next", 9)]);
+        assert_eq!(counter_deltas(&before, &after), expected);
+    }
+
+    #[test]
+    fn window_subtracts_the_snapshot_overhead_from_session_counters_only() {
+        // Reading the counters sends bytes and scans, and other sessions read rows
+        let first = counters(&[("Bytes_sent", 1000), ("Select_scan", 10), ("Innodb_rows_read", 500)]);
+        let second = counters(&[("Bytes_sent", 1200), ("Select_scan", 11), ("Innodb_rows_read", 540)]);
+        let mut window = CounterWindow::new(&first, second.clone());
+        assert_eq!(window.overhead, counters(&[("Bytes_sent", 200), ("Select_scan", 1)]));
+
+        let after = counters(&[("Bytes_sent", 1700), ("Select_scan", 13), ("Innodb_rows_read", 640)]);
+        window.add_run(&after);
+
+        let deltas = window.close();
+        assert_eq!(deltas.session, counters(&[("Bytes_sent", 300), ("Select_scan", 1)]));
+        assert_eq!(deltas.server, counters(&[("Innodb_rows_read", 100)]));
+    }
+
+    #[test]
+    fn window_sums_the_measured_runs_only() {
+        let first = counters(&[("Handler_read_rnd_next", 0), ("Innodb_buffer_pool_reads", 0)]);
+        let second = counters(&[("Handler_read_rnd_next", 2), ("Innodb_buffer_pool_reads", 0)]);
+        let mut window = CounterWindow::new(&first, second);
+
+        // Two runs of 50 rows each, with a cache eviction between them that
+        // happens outside the window
+        for (start, end) in [(2, 54), (80, 132)] {
+            window.before = counters(&[("Handler_read_rnd_next", start), ("Innodb_buffer_pool_reads", start)]);
+            window.add_run(&counters(&[("Handler_read_rnd_next", end), ("Innodb_buffer_pool_reads", end)]));
+        }
+
+        let deltas = window.close();
+        assert_eq!(deltas.session, counters(&[("Handler_read_rnd_next", 100)]));
+        assert_eq!(deltas.server, counters(&[("Innodb_buffer_pool_reads", 104)]));
+    }
+
+    #[test]
+    fn window_without_runs_is_empty() {
+        let snapshot = counters(&[("Bytes_sent", 10), ("Innodb_rows_read", 20)]);
+        let deltas = CounterWindow::new(&snapshot, snapshot.clone()).close();
+        assert!(deltas.session.is_empty());
+        assert!(deltas.server.is_empty());
+    }
+}
@@ -0,0 +1,2479 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::database::HasArguments;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::compression::Compression;
+use crate::db::{with_pool, Backend, Db, Timestamp};
+use crate::explain::{self, ExplainDatabase};
+use crate::models::{Achievement, ComplexPreferences, ComplexProfile, ComplexUser, Metadata, Statistics, User, UserProfile};
+use crate::parser::JsonParser;
+
+/// Physical layout a `UserStore` persists users in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    Column,
+    Json,
+    /// Postgres only: binary JSONB document.
+    Jsonb,
+    /// Postgres only: JSONB document with a GIN index over `data`.
+    JsonbGin,
+    /// JSON document plus indexed generated columns for email and age.
+    Hybrid,
+    /// Scalar columns, with preferences and social links in child tables.
+    Normalized,
+    /// The `json` document encoded as MessagePack, in a BLOB.
+    #[serde(rename = "msgpack")]
+    MessagePack,
+    /// The `json` document encoded as CBOR, in a BLOB.
+    Cbor,
+    /// The `json` document encoded with bincode, in a BLOB.
+    Bincode,
+    /// The `json` document compressed with zstd, in a BLOB.
+    JsonZstd,
+    /// The `json` document compressed with LZ4, in a BLOB.
+    JsonLz4,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 11] = [
+        Layout::Column,
+        Layout::Json,
+        Layout::Jsonb,
+        Layout::JsonbGin,
+        Layout::Hybrid,
+        Layout::Normalized,
+        Layout::MessagePack,
+        Layout::Cbor,
+        Layout::Bincode,
+        Layout::JsonZstd,
+        Layout::JsonLz4,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::Column => "column",
+            Layout::Json => "json",
+            Layout::Jsonb => "jsonb",
+            Layout::JsonbGin => "jsonb_gin",
+            Layout::Hybrid => "hybrid",
+            Layout::Normalized => "normalized",
+            Layout::MessagePack => "msgpack",
+            Layout::Cbor => "cbor",
+            Layout::Bincode => "bincode",
+            Layout::JsonZstd => "json_zstd",
+            Layout::JsonLz4 => "json_lz4",
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            Layout::Column => "users_column",
+            Layout::Json => "users_json",
+            Layout::Jsonb => "users_jsonb",
+            Layout::JsonbGin => "users_jsonb_gin",
+            Layout::Hybrid => "users_hybrid",
+            Layout::Normalized => "users_normalized",
+            Layout::MessagePack => "users_msgpack",
+            Layout::Cbor => "users_cbor",
+            Layout::Bincode => "users_bincode",
+            Layout::JsonZstd => "users_json_zstd",
+            Layout::JsonLz4 => "users_json_lz4",
+        }
+    }
+
+    pub fn is_supported_by(&self, backend: Backend) -> bool {
+        match self {
+            Layout::Column
+            | Layout::Json
+            | Layout::Hybrid
+            | Layout::Normalized
+            | Layout::MessagePack
+            | Layout::Cbor
+            | Layout::Bincode
+            | Layout::JsonZstd
+            | Layout::JsonLz4 => true,
+            Layout::Jsonb | Layout::JsonbGin => backend == Backend::Postgres,
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layout::ALL
+            .into_iter()
+            .find(|layout| layout.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown layout: {}", s))
+    }
+}
+
+/// Most bind parameters a single statement may carry on every backend
+/// (SQLite's default `SQLITE_MAX_VARIABLE_NUMBER`).
+const MAX_BIND_PARAMS: usize = 32766;
+
+/// `VALUES` list of `rows` copies of the `row` placeholder tuple.
+fn values_list(row: &str, rows: usize) -> String {
+    vec![row; rows].join(", ")
+}
+
+/// How a set of rows is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// One autocommitted statement per row.
+    #[default]
+    Single,
+    /// One statement per row inside a single transaction.
+    Transactional,
+    /// Multi-row statements of up to `batch_size` rows inside a single
+    /// transaction.
+    Batched,
+}
+
+impl WriteMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WriteMode::Single => "single",
+            WriteMode::Transactional => "transactional",
+            WriteMode::Batched => "batched",
+        }
+    }
+
+    /// Rows per statement, capped so a statement stays under the bind
+    /// parameter limit.
+    fn rows_per_statement(&self, batch_size: usize, params_per_row: usize) -> usize {
+        match self {
+            WriteMode::Batched => batch_size.clamp(1, MAX_BIND_PARAMS / params_per_row),
+            WriteMode::Single | WriteMode::Transactional => 1,
+        }
+    }
+}
+
+/// Runs one statement per chunk of `$per_statement` rows on `$executor`.
+/// Only the first and a shorter last chunk differ in SQL, so only those
+/// are offered to `explain::record`.
+macro_rules! execute_chunks {
+    ($executor:expr, $rows:expr, $per_statement:expr, $sql:expr, |$query:ident, $chunk:ident| $bind:block) => {
+        let mut previous_len = None;
+        for $chunk in $rows.chunks($per_statement) {
+            let sql = $sql($chunk.len());
+            if previous_len != Some($chunk.len()) {
+                previous_len = Some($chunk.len());
+                explain::record($executor, || {
+                    let mut $query = sqlx::query(&sql);
+                    $bind
+                    $query
+                })
+                .await?;
+            }
+            let mut $query = sqlx::query(&sql);
+            $bind
+            $query.execute($executor).await?;
+        }
+    };
+}
+
+/// Writes `$rows` in chunks of `$per_statement` according to `$mode`.
+///
+/// `$sql` builds the statement for a chunk of `n` rows and `$bind` binds
+/// `$chunk` onto `$query`. Several `;`-separated row sets are written in
+/// order, sharing the transaction unless `$mode` is `Single`.
+macro_rules! execute_rows {
+    ($db:expr, $mode:expr, $($rows:expr, $per_statement:expr, $sql:expr, |$query:ident, $chunk:ident| $bind:block);+ $(;)?) => {
+        with_pool!($db, pool => {
+            if $mode == WriteMode::Single {
+                $(execute_chunks!(pool, $rows, $per_statement, $sql, |$query, $chunk| $bind);)+
+            } else {
+                let mut tx = pool.begin().await?;
+                $(execute_chunks!(&mut tx, $rows, $per_statement, $sql, |$query, $chunk| $bind);)+
+                tx.commit().await?;
+            }
+        })
+    };
+}
+
+/// Where the time of a fetch went.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchPhases {
+    /// Until the first row arrived, i.e. server-side execution.
+    pub query: Duration,
+    /// Receiving the remaining rows.
+    pub transfer: Duration,
+    /// `Row::get` into strings and integers.
+    pub decode: Duration,
+    /// Decompressing documents the application compressed.
+    pub decompress: Duration,
+    /// Parsing the decoded JSON text, or deserializing the decoded BLOB.
+    pub parse: Duration,
+    /// Until the first row was decoded and parsed, i.e. could be handed on.
+    /// Only the stores with a `FetchMode` report it.
+    pub first_parsed: Option<Duration>,
+    /// Documents that did not parse as a `User`, e.g. complex profiles in
+    /// the same table, and were skipped.
+    pub skipped: usize,
+}
+
+impl std::ops::AddAssign for FetchPhases {
+    fn add_assign(&mut self, other: Self) {
+        self.query += other.query;
+        self.transfer += other.transfer;
+        self.decode += other.decode;
+        self.decompress += other.decompress;
+        self.parse += other.parse;
+        self.first_parsed = self.first_parsed.or(other.first_parsed);
+        self.skipped += other.skipped;
+    }
+}
+
+/// `fetch_all` that notes when the first row arrived, to split server query
+/// time from row transfer. `query` builds the statement; it is built once
+/// more, to be explained, while `explain::capture` records plans.
+async fn fetch_all_timed<'q, DB, A, E>(
+    query: impl Fn() -> sqlx::query::Query<'q, DB, A>,
+    executor: E,
+    phases: &mut FetchPhases,
+) -> anyhow::Result<Vec<DB::Row>>
+where
+    DB: ExplainDatabase,
+    A: 'q + sqlx::IntoArguments<'q, DB>,
+    E: 'q + sqlx::Executor<'q, Database = DB> + Copy,
+{
+    explain::record(executor, &query).await?;
+
+    let start = Instant::now();
+    let mut stream = query().fetch(executor);
+    let mut rows = Vec::new();
+    let mut first_row = None;
+    while let Some(row) = stream.try_next().await? {
+        first_row.get_or_insert_with(|| start.elapsed());
+        rows.push(row);
+    }
+    let total = start.elapsed();
+
+    phases.query = first_row.unwrap_or(total);
+    phases.transfer = total - phases.query;
+    Ok(rows)
+}
+
+/// Streaming counterpart of `fetch_all_timed`: hands each row to `on_row`
+/// as it arrives instead of collecting them. `transfer` excludes the time
+/// spent in `on_row`, which the caller accounts for.
+async fn fetch_each_timed<'q, DB, A, E>(
+    query: impl Fn() -> sqlx::query::Query<'q, DB, A>,
+    executor: E,
+    phases: &mut FetchPhases,
+    mut on_row: impl FnMut(DB::Row) -> anyhow::Result<()>,
+) -> anyhow::Result<()>
+where
+    DB: ExplainDatabase,
+    A: 'q + sqlx::IntoArguments<'q, DB>,
+    E: 'q + sqlx::Executor<'q, Database = DB> + Copy,
+{
+    explain::record(executor, &query).await?;
+
+    let start = Instant::now();
+    let mut stream = query().fetch(executor);
+    let mut first_row = None;
+    let mut handling = Duration::ZERO;
+    while let Some(row) = stream.try_next().await? {
+        first_row.get_or_insert_with(|| start.elapsed());
+        let handle_start = Instant::now();
+        on_row(row)?;
+        handling += handle_start.elapsed();
+    }
+    let total = start.elapsed();
+
+    phases.query = first_row.unwrap_or(total);
+    phases.transfer = total.saturating_sub(phases.query + handling);
+    Ok(())
+}
+
+/// Whether a read collects every row before decoding them (`fetch_all`) or
+/// decodes and parses each row as it arrives (`fetch`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchMode {
+    #[default]
+    Buffered,
+    /// Only the parsed results are held, not the rows they came from.
+    Streaming,
+}
+
+impl FetchMode {
+    pub const ALL: [FetchMode; 2] = [FetchMode::Buffered, FetchMode::Streaming];
+
+    /// `name` suffixed with the mode unless it is the default.
+    pub fn label(&self, name: &str) -> String {
+        match self {
+            FetchMode::Buffered => name.to_string(),
+            FetchMode::Streaming => format!("{}+streaming", name),
+        }
+    }
+}
+
+/// Which users a `fetch` reads.
+#[derive(Debug, Clone)]
+pub enum UserFilter {
+    /// Newest `limit` users, ordered by `created_at DESC`.
+    Latest { limit: i32 },
+    /// Point lookup by email.
+    Email(String),
+    /// Up to `limit` users with `min <= age <= max`, in no particular order.
+    AgeRange { min: i32, max: i32, limit: i32 },
+}
+
+impl UserFilter {
+    /// `WHERE`/`ORDER BY`/`LIMIT` tail of the select, given the layout's
+    /// email predicate and age expression.
+    fn clause(&self, email_predicate: &str, age: &str) -> String {
+        match self {
+            UserFilter::Latest { .. } => "ORDER BY created_at DESC LIMIT ?".to_string(),
+            UserFilter::Email(_) => format!("WHERE {}", email_predicate),
+            UserFilter::AgeRange { .. } => format!("WHERE {} BETWEEN ? AND ? LIMIT ?", age),
+        }
+    }
+
+    fn bind<'q, DB>(
+        self,
+        query: sqlx::query::Query<'q, DB, <DB as HasArguments<'q>>::Arguments>,
+    ) -> sqlx::query::Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: sqlx::Database,
+        i32: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        match self {
+            UserFilter::Latest { limit } => query.bind(limit),
+            UserFilter::Email(email) => query.bind(email),
+            UserFilter::AgeRange { min, max, limit } => query.bind(min).bind(max).bind(limit),
+        }
+    }
+}
+
+/// Number of users per age, as computed by `UserStore::age_histogram`.
+pub type AgeHistogram = Vec<(i64, i64)>;
+
+/// `SELECT age, COUNT(*)` grouped on `age`, which is the layout's expression
+/// index when it has one.
+fn age_histogram_sql(backend: Backend, table: &str, age: &str) -> String {
+    format!(
+        "SELECT {} AS age, users FROM (SELECT {} AS age, COUNT(*) AS users FROM {} GROUP BY {}) AS histogram ORDER BY 1",
+        backend.bigint("age"),
+        age,
+        table,
+        age
+    )
+}
+
+/// `DELETE` by primary key, shared by every layout.
+async fn delete_by_id(db: &Db, table: &str, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    let backend = db.backend();
+    let delete_sql = |rows: usize| {
+        let sql = format!("DELETE FROM {} WHERE id IN ({})", table, values_list(backend.id_param(), rows));
+        backend.sql(&sql).into_owned()
+    };
+    let per_statement = mode.rows_per_statement(batch_size, 1);
+
+    execute_rows!(db, mode, ids, per_statement, delete_sql, |query, chunk| {
+        for id in chunk {
+            query = query.bind(id);
+        }
+    });
+
+    Ok(())
+}
+
+/// Sets `age` by id on a layout with a plain `age` column.
+async fn update_age(db: &Db, table: &str, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+    let backend = db.backend();
+    let update_sql = |rows: usize| {
+        let sql = format!(
+            "UPDATE {} SET age = CASE id {} END WHERE id IN ({})",
+            table,
+            vec!["WHEN ? THEN ?"; rows].join(" "),
+            values_list(backend.id_param(), rows)
+        );
+        backend.sql(&sql).into_owned()
+    };
+    let per_statement = mode.rows_per_statement(batch_size, 3);
+
+    execute_rows!(db, mode, users, per_statement, update_sql, |query, chunk| {
+        for user in chunk {
+            query = query.bind(user.id.to_string()).bind(user.age);
+        }
+        for user in chunk {
+            query = query.bind(user.id.to_string());
+        }
+    });
+
+    Ok(())
+}
+
+/// Summed `byte_length` of the comma-separated `columns` over every row of
+/// `table`.
+async fn stored_bytes(db: &Db, table: &str, columns: &str) -> anyhow::Result<i64> {
+    let backend = db.backend();
+    let row_bytes: Vec<String> = columns
+        .split(", ")
+        .map(|column| format!("COALESCE({}, 0)", backend.byte_length(column)))
+        .collect();
+    let sql = format!(
+        "SELECT {} FROM {}",
+        backend.bigint(&format!("COALESCE(SUM({}), 0)", row_bytes.join(" + "))),
+        table
+    );
+
+    let bytes = with_pool!(db, pool => {
+        sqlx::query_scalar(&sql)
+            .fetch_one(pool)
+            .await?
+    });
+    Ok(bytes)
+}
+
+/// Storage operations shared by every layout, so handlers and benchmarks are
+/// written once and the layout is picked at runtime.
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    fn layout(&self) -> Layout;
+
+    fn db(&self) -> &Db;
+
+    /// Name results are reported under; distinguishes the `ReadOptions` of
+    /// a layout that has several.
+    fn storage_type(&self) -> String {
+        self.layout().as_str().to_string()
+    }
+
+    async fn insert(&self, user: &User) -> anyhow::Result<()>;
+
+    /// Inserts `users`; `batch_size` only applies to `WriteMode::Batched`.
+    async fn insert_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()>;
+
+    /// Persists the new `age` of each user, matched by id. Document layouts
+    /// rewrite the whole document.
+    async fn update_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()>;
+
+    async fn delete_rows(&self, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()>;
+
+    /// Newest `limit` users, ordered by `created_at DESC`.
+    async fn fetch_latest(&self, limit: i32) -> anyhow::Result<Vec<User>> {
+        Ok(self.fetch(UserFilter::Latest { limit }).await?.0)
+    }
+
+    /// Users matching `filter`, with the time spent in each phase.
+    async fn fetch(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)>;
+
+    async fn age_histogram(&self) -> anyhow::Result<AgeHistogram>;
+
+    async fn count(&self) -> anyhow::Result<i64>;
+
+    /// Bytes of every stored user, summed over the columns of all of the
+    /// layout's tables.
+    async fn stored_bytes(&self) -> anyhow::Result<i64>;
+
+    async fn clear(&self) -> anyhow::Result<()>;
+}
+
+/// How a store reads users back, taken from the query string of the read
+/// endpoints. Each layout ignores the options it has no use for.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ReadOptions {
+    /// `normalized` only.
+    #[serde(default)]
+    pub strategy: FetchStrategy,
+    /// Document layouts only.
+    #[serde(default)]
+    pub parser: JsonParser,
+    /// `column` and the uncompressed JSON layouts only.
+    #[serde(default)]
+    pub fetch: FetchMode,
+}
+
+impl ReadOptions {
+    /// Every variant of reading `layout` that `bench` compares.
+    pub fn variants(layout: Layout) -> Vec<ReadOptions> {
+        match layout {
+            Layout::Column => FetchMode::ALL
+                .into_iter()
+                .map(|fetch| ReadOptions {
+                    fetch,
+                    ..ReadOptions::default()
+                })
+                .collect(),
+            Layout::Json | Layout::Jsonb | Layout::JsonbGin | Layout::Hybrid => {
+                let mut variants: Vec<ReadOptions> = JsonParser::ALL
+                    .iter()
+                    .map(|&parser| ReadOptions {
+                        parser,
+                        ..ReadOptions::default()
+                    })
+                    .collect();
+                variants.push(ReadOptions {
+                    fetch: FetchMode::Streaming,
+                    ..ReadOptions::default()
+                });
+                variants
+            }
+            Layout::JsonZstd | Layout::JsonLz4 => JsonParser::ALL
+                .iter()
+                .map(|&parser| ReadOptions {
+                    parser,
+                    ..ReadOptions::default()
+                })
+                .collect(),
+            Layout::Normalized => FetchStrategy::ALL
+                .into_iter()
+                .map(|strategy| ReadOptions {
+                    strategy,
+                    ..ReadOptions::default()
+                })
+                .collect(),
+            Layout::MessagePack | Layout::Cbor | Layout::Bincode => vec![ReadOptions::default()],
+        }
+    }
+}
+
+pub fn for_layout(layout: Layout, db: Db) -> anyhow::Result<Arc<dyn UserStore>> {
+    for_layout_with(layout, db, ReadOptions::default())
+}
+
+/// Like `for_layout`, reading with `read` instead of the defaults.
+pub fn for_layout_with(layout: Layout, db: Db, read: ReadOptions) -> anyhow::Result<Arc<dyn UserStore>> {
+    if !layout.is_supported_by(db.backend()) {
+        anyhow::bail!("layout {} is not available on {}", layout.as_str(), db.backend().as_str());
+    }
+
+    Ok(match layout {
+        Layout::Column => Arc::new(ColumnStore::new(db).with_fetch(read.fetch)),
+        Layout::Json | Layout::Jsonb | Layout::JsonbGin | Layout::Hybrid => Arc::new(
+            JsonStore::with_layout(db, layout)
+                .with_parser(read.parser)
+                .with_fetch(read.fetch),
+        ),
+        Layout::Normalized => Arc::new(NormalizedStore::with_strategy(db, read.strategy)),
+        Layout::MessagePack => Arc::new(BlobStore::new(db, BlobFormat::MessagePack)),
+        Layout::Cbor => Arc::new(BlobStore::new(db, BlobFormat::Cbor)),
+        Layout::Bincode => Arc::new(BlobStore::new(db, BlobFormat::Bincode)),
+        Layout::JsonZstd => Arc::new(BlobStore::new(db, BlobFormat::JsonZstd).with_parser(read.parser)),
+        Layout::JsonLz4 => Arc::new(BlobStore::new(db, BlobFormat::JsonLz4).with_parser(read.parser)),
+    })
+}
+
+/// Raw JSON documents of any shape, such as the complex profiles, read back
+/// as text for the caller to parse.
+#[async_trait]
+pub trait DocumentStore: UserStore {
+    fn parser(&self) -> JsonParser;
+
+    /// `name` labelled with the parser and compression of this store.
+    fn label(&self, name: &str) -> String;
+
+    /// Stores `documents` in one transaction, `batch_size` per INSERT.
+    async fn insert_documents(&self, documents: &[(String, serde_json::Value)], batch_size: usize) -> anyhow::Result<()>;
+
+    /// Newest `limit` documents, unparsed, with the time spent in each phase
+    /// before parsing.
+    async fn fetch_documents(&self, limit: i32) -> anyhow::Result<(Vec<String>, FetchPhases)>;
+
+    /// Whether `stream_documents` hands documents on as they arrive.
+    fn fetch_mode(&self) -> FetchMode {
+        FetchMode::Buffered
+    }
+
+    /// Hands the newest `limit` documents to `on_document`, as they arrive
+    /// when the store streams. Sets every phase but `parse`.
+    async fn stream_documents(
+        &self,
+        limit: i32,
+        on_document: &mut (dyn FnMut(String) -> anyhow::Result<()> + Send),
+    ) -> anyhow::Result<FetchPhases> {
+        let (documents, phases) = self.fetch_documents(limit).await?;
+        for document in documents {
+            on_document(document)?;
+        }
+        Ok(phases)
+    }
+}
+
+/// Document store for `compression`: `users_json`, or the compressed
+/// `users_json_zstd` and `users_json_lz4`, which are always read buffered.
+pub fn documents_for(compression: Compression, db: Db, parser: JsonParser, fetch: FetchMode) -> Arc<dyn DocumentStore> {
+    match compression {
+        Compression::None => Arc::new(JsonStore::new(db).with_parser(parser).with_fetch(fetch)),
+        Compression::Zstd => Arc::new(BlobStore::new(db, BlobFormat::JsonZstd).with_parser(parser)),
+        Compression::Lz4 => Arc::new(BlobStore::new(db, BlobFormat::JsonLz4).with_parser(parser)),
+    }
+}
+
+/// `users_column`: one column per scalar field, nested profile data as JSON.
+pub struct ColumnStore {
+    db: Db,
+    fetch: FetchMode,
+}
+
+impl ColumnStore {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            fetch: FetchMode::default(),
+        }
+    }
+
+    pub fn with_fetch(self, fetch: FetchMode) -> Self {
+        Self { fetch, ..self }
+    }
+
+    /// `fetch` for `FetchMode::Streaming`: each row is decoded and parsed as
+    /// soon as it arrives.
+    async fn fetch_streaming(&self, sql: &str, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        let started = Instant::now();
+        let mut phases = FetchPhases::default();
+        let mut users = Vec::new();
+        let mut decode = Duration::ZERO;
+        let mut parse = Duration::ZERO;
+        let mut first_parsed = None;
+
+        with_pool!(&self.db, pool => {
+            fetch_each_timed(|| filter.clone().bind(sqlx::query(sql)), pool, &mut phases, |row| {
+                let start = Instant::now();
+                let row = ColumnRow::decode(&row);
+                decode += start.elapsed();
+
+                let start = Instant::now();
+                users.push(row.into_user()?);
+                parse += start.elapsed();
+                first_parsed.get_or_insert_with(|| started.elapsed());
+                Ok(())
+            })
+            .await?;
+        });
+
+        phases.decode = decode;
+        phases.parse = parse;
+        phases.first_parsed = first_parsed;
+        Ok((users, phases))
+    }
+}
+
+/// A `users_column` row decoded from the driver, JSON columns still as text.
+struct ColumnRow {
+    id: String,
+    name: String,
+    email: String,
+    age: i32,
+    bio: String,
+    avatar_url: Option<String>,
+    preferences: String,
+    social_links: String,
+    created_at: OffsetDateTime,
+}
+
+impl ColumnRow {
+    fn decode<R>(row: &R) -> Self
+    where
+        R: Row,
+        for<'a> &'a str: sqlx::ColumnIndex<R>,
+        for<'r> String: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+        for<'r> Option<String>: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+        for<'r> i32: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+        for<'r> OffsetDateTime: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    {
+        ColumnRow {
+            id: row.get("id"),
+            name: row.get("name"),
+            email: row.get("email"),
+            age: row.get("age"),
+            bio: row.get("bio"),
+            avatar_url: row.get("avatar_url"),
+            preferences: row.get("preferences"),
+            social_links: row.get("social_links"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    /// Parses the JSON columns into a `User`.
+    fn into_user(self) -> anyhow::Result<User> {
+        let preferences: HashMap<String, String> = serde_json::from_str(&self.preferences)
+            .with_context(|| format!("preferences of user {}", self.id))?;
+        let social_links: Vec<String> = serde_json::from_str(&self.social_links)
+            .with_context(|| format!("social_links of user {}", self.id))?;
+
+        Ok(User {
+            id: Uuid::parse_str(&self.id)?,
+            name: self.name,
+            email: self.email,
+            age: self.age,
+            profile: UserProfile {
+                bio: self.bio,
+                avatar_url: self.avatar_url,
+                preferences,
+                social_links,
+            },
+            created_at: self.created_at,
+        })
+    }
+}
+
+#[async_trait]
+impl UserStore for ColumnStore {
+    fn layout(&self) -> Layout {
+        Layout::Column
+    }
+
+    fn db(&self) -> &Db {
+        &self.db
+    }
+
+    fn storage_type(&self) -> String {
+        self.fetch.label(Layout::Column.as_str())
+    }
+
+    async fn insert(&self, user: &User) -> anyhow::Result<()> {
+        let preferences = serde_json::to_string(&user.profile.preferences)?;
+        let social_links = serde_json::to_string(&user.profile.social_links)?;
+        let backend = self.db.backend();
+        let json = backend.json_param("JSONB");
+        let sql = format!(
+            r#"
+            INSERT INTO users_column (id, name, email, age, bio, avatar_url, preferences, social_links, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, {}, {}, ?)
+            "#,
+            json, json
+        );
+        let sql = backend.sql(&sql);
+
+        with_pool!(&self.db, pool => {
+            sqlx::query(&sql)
+            .bind(user.id.to_string())
+            .bind(&user.name)
+            .bind(&user.email)
+            .bind(user.age)
+            .bind(&user.profile.bio)
+            .bind(&user.profile.avatar_url)
+            .bind(&preferences)
+            .bind(&social_links)
+            .bind(Timestamp(user.created_at))
+            .execute(pool)
+            .await?;
+        });
+
+        Ok(())
+    }
+
+    async fn insert_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| {
+                Ok((
+                    user,
+                    serde_json::to_string(&user.profile.preferences)?,
+                    serde_json::to_string(&user.profile.social_links)?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let backend = self.db.backend();
+        let json = backend.json_param("JSONB");
+        let row = format!("(?, ?, ?, ?, ?, ?, {}, {}, ?)", json, json);
+        let insert_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO users_column (id, name, email, age, bio, avatar_url, preferences, social_links, created_at) VALUES {}",
+                values_list(&row, rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let per_statement = mode.rows_per_statement(batch_size, 9);
+
+        execute_rows!(&self.db, mode, rows, per_statement, insert_sql, |query, chunk| {
+            for (user, preferences, social_links) in chunk {
+                query = query
+                    .bind(user.id.to_string())
+                    .bind(&user.name)
+                    .bind(&user.email)
+                    .bind(user.age)
+                    .bind(&user.profile.bio)
+                    .bind(&user.profile.avatar_url)
+                    .bind(preferences)
+                    .bind(social_links)
+                    .bind(Timestamp(user.created_at));
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn update_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        update_age(&self.db, Layout::Column.table(), users, mode, batch_size).await
+    }
+
+    async fn delete_rows(&self, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        delete_by_id(&self.db, Layout::Column.table(), ids, mode, batch_size).await
+    }
+
+    async fn fetch(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        let backend = self.db.backend();
+        let sql = format!(
+            r#"
+            SELECT id, name, email, age, bio, avatar_url, {}, {}, created_at
+            FROM users_column
+            {}
+            "#,
+            backend.json_text("preferences"),
+            backend.json_text("social_links"),
+            filter.clause("email = ?", "age")
+        );
+        let sql = backend.sql(&sql);
+        if self.fetch == FetchMode::Streaming {
+            return self.fetch_streaming(&sql, filter).await;
+        }
+        let started = Instant::now();
+        let mut phases = FetchPhases::default();
+
+        let decoded: Vec<ColumnRow> = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| filter.clone().bind(sqlx::query(&sql)), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let decoded = rows.iter().map(ColumnRow::decode).collect();
+            phases.decode = start.elapsed();
+            decoded
+        });
+
+        let start = Instant::now();
+        let mut first_parsed = None;
+        let users = decoded
+            .into_iter()
+            .map(ColumnRow::into_user)
+            .inspect(|_| {
+                first_parsed.get_or_insert_with(|| started.elapsed());
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        phases.parse = start.elapsed();
+        phases.first_parsed = first_parsed;
+
+        Ok((users, phases))
+    }
+
+    async fn age_histogram(&self) -> anyhow::Result<AgeHistogram> {
+        let sql = age_histogram_sql(self.db.backend(), Layout::Column.table(), "age");
+
+        let histogram = with_pool!(&self.db, pool => {
+            explain::record(pool, || sqlx::query(&sql)).await?;
+            sqlx::query_as(&sql)
+                .fetch_all(pool)
+                .await?
+        });
+        Ok(histogram)
+    }
+
+    async fn count(&self) -> anyhow::Result<i64> {
+        let count = with_pool!(&self.db, pool => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM users_column")
+                .fetch_one(pool)
+                .await?
+        });
+        Ok(count)
+    }
+
+    async fn stored_bytes(&self) -> anyhow::Result<i64> {
+        stored_bytes(
+            &self.db,
+            Layout::Column.table(),
+            "id, name, email, age, bio, avatar_url, preferences, social_links, created_at",
+        )
+        .await
+    }
+
+    async fn clear(&self) -> anyhow::Result<()> {
+        with_pool!(&self.db, pool => {
+            sqlx::query("DELETE FROM users_column")
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+}
+
+/// `users_json` (and the Postgres `users_jsonb*` tables): the whole user
+/// serialized into a single JSON document. `users_hybrid` stores the same
+/// document and lets the database derive indexed email and age columns.
+pub struct JsonStore {
+    db: Db,
+    layout: Layout,
+    parser: JsonParser,
+    fetch: FetchMode,
+}
+
+impl JsonStore {
+    pub fn new(db: Db) -> Self {
+        Self::with_layout(db, Layout::Json)
+    }
+
+    pub fn with_layout(db: Db, layout: Layout) -> Self {
+        Self {
+            db,
+            layout,
+            parser: JsonParser::default(),
+            fetch: FetchMode::default(),
+        }
+    }
+
+    /// Parses documents with `parser` instead of serde_json.
+    pub fn with_parser(self, parser: JsonParser) -> Self {
+        Self { parser, ..self }
+    }
+
+    pub fn with_fetch(self, fetch: FetchMode) -> Self {
+        Self { fetch, ..self }
+    }
+
+    fn json_type(&self) -> &'static str {
+        match self.layout {
+            Layout::Jsonb | Layout::JsonbGin | Layout::Hybrid => "JSONB",
+            _ => "JSON",
+        }
+    }
+
+    /// Document predicate for an email lookup, written like the expression
+    /// indexes of each migration set so the planner can use them. `hybrid`
+    /// filters on its generated column instead.
+    fn email_predicate(&self) -> &'static str {
+        match (self.db.backend(), self.layout) {
+            (_, Layout::Hybrid) => "email = ?",
+            // jsonb_path_ops only serves containment
+            (Backend::Postgres, Layout::JsonbGin) => "data @> CAST(? AS JSONB)",
+            (Backend::Postgres, _) => "data->>'email' = ?",
+            (Backend::Sqlite, _) => "json_extract(data, '$.email') = ?",
+            // Charset and collation are spelled out like in the index, as a
+            // bare CAST takes them from the session that runs it
+            (Backend::MySql, _) => {
+                "CAST(JSON_UNQUOTE(JSON_EXTRACT(data, '$.email')) AS CHAR(255) CHARACTER SET utf8mb4) \
+                 COLLATE utf8mb4_bin = ?"
+            }
+        }
+    }
+
+    /// Parameter bound into `email_predicate`.
+    fn email_param(&self, email: String) -> String {
+        match self.layout {
+            Layout::JsonbGin => serde_json::json!({ "email": email }).to_string(),
+            _ => email,
+        }
+    }
+
+    /// Document age expression, matching the expression indexes (or the
+    /// generated column for `hybrid`).
+    fn age_expr(&self) -> &'static str {
+        if self.layout == Layout::Hybrid {
+            return "age";
+        }
+        match self.db.backend() {
+            Backend::Postgres => "(data->>'age')::INTEGER",
+            Backend::Sqlite => "json_extract(data, '$.age')",
+            Backend::MySql => "CAST(JSON_EXTRACT(data, '$.age') AS UNSIGNED)",
+        }
+    }
+
+    /// Runs the complex-profile analyses of `benchmark::complex_processing`
+    /// in SQL over the newest `limit` documents: engagement rate, tag
+    /// analysis, achievement points and bio statistics. Documents without the
+    /// complex fields yield `NULL`s.
+    pub async fn aggregate_complex(&self, limit: i32) -> anyhow::Result<(Vec<ComplexAggregate>, FetchPhases)> {
+        let backend = self.db.backend();
+        let latest = format!("SELECT id, data FROM {} ORDER BY created_at DESC LIMIT ?", self.layout.table());
+        // One row per document with the raw values each analysis needs
+        let extracted = match backend {
+            Backend::MySql => format!(
+                r#"
+                SELECT id,
+                       CAST(JSON_EXTRACT(data, '$.profile.statistics.posts_count') AS SIGNED) AS posts,
+                       CAST(JSON_EXTRACT(data, '$.profile.statistics.followers_count') AS SIGNED) AS followers,
+                       JSON_LENGTH(data, '$.metadata.tags') AS total_tags,
+                       (SELECT COUNT(*)
+                        FROM JSON_TABLE(data, '$.metadata.tags[*]' COLUMNS (tag VARCHAR(255) PATH '$')) AS tags
+                        WHERE tag = 'verified') AS verified_tags,
+                       (SELECT SUM(points)
+                        FROM JSON_TABLE(data, '$.profile.achievements[*]' COLUMNS (points BIGINT PATH '$.points')) AS achievements
+                       ) AS achievement_points,
+                       JSON_UNQUOTE(JSON_EXTRACT(data, '$.profile.bio')) AS bio,
+                       CHAR_LENGTH(JSON_UNQUOTE(JSON_EXTRACT(data, '$.profile.bio'))) AS bio_chars
+                FROM ({}) AS latest
+                "#,
+                latest
+            ),
+            Backend::Sqlite => format!(
+                r#"
+                SELECT id,
+                       json_extract(data, '$.profile.statistics.posts_count') AS posts,
+                       json_extract(data, '$.profile.statistics.followers_count') AS followers,
+                       json_array_length(data, '$.metadata.tags') AS total_tags,
+                       (SELECT COUNT(*) FROM json_each(data, '$.metadata.tags') WHERE value = 'verified') AS verified_tags,
+                       (SELECT SUM(json_extract(value, '$.points'))
+                        FROM json_each(data, '$.profile.achievements')) AS achievement_points,
+                       json_extract(data, '$.profile.bio') AS bio,
+                       length(json_extract(data, '$.profile.bio')) AS bio_chars
+                FROM ({}) AS latest
+                "#,
+                latest
+            ),
+            Backend::Postgres => {
+                let json = self.json_type().to_lowercase();
+                format!(
+                    r#"
+                    SELECT id,
+                           (data->'profile'->'statistics'->>'posts_count')::BIGINT AS posts,
+                           (data->'profile'->'statistics'->>'followers_count')::BIGINT AS followers,
+                           {json}_array_length(data->'metadata'->'tags') AS total_tags,
+                           (SELECT COUNT(*) FROM {json}_array_elements_text(data->'metadata'->'tags') AS tag
+                            WHERE tag = 'verified') AS verified_tags,
+                           (SELECT SUM((achievement->>'points')::BIGINT)
+                            FROM {json}_array_elements(data->'profile'->'achievements') AS achievement
+                           ) AS achievement_points,
+                           data->'profile'->>'bio' AS bio,
+                           char_length(data->'profile'->>'bio') AS bio_chars
+                    FROM ({latest}) AS latest
+                    "#,
+                )
+            }
+        };
+        // Words approximated as one more than the spaces, where Rust splits on
+        // any whitespace. ' ' and '.' are single bytes, so byte and character
+        // lengths agree
+        let words = "CASE WHEN bio = '' THEN 0 ELSE LENGTH(bio) - LENGTH(REPLACE(bio, ' ', '')) + 1 END";
+        let sentences = "LENGTH(bio) - LENGTH(REPLACE(bio, '.', ''))";
+        let sql = format!(
+            r#"
+            SELECT id,
+                   {} AS engagement_rate,
+                   {} AS total_tags,
+                   {} AS verified_tags,
+                   {} AS verification_rate,
+                   {} AS achievement_points,
+                   {} AS word_count,
+                   {} AS char_count,
+                   {} AS sentence_count,
+                   {} AS avg_words_per_sentence
+            FROM ({}) AS extracted
+            "#,
+            backend.double(
+                "CASE WHEN posts IS NULL OR followers IS NULL THEN NULL \
+                 WHEN followers > 0 THEN posts * 100.0 / followers ELSE 0 END"
+            ),
+            backend.bigint("total_tags"),
+            backend.bigint("CASE WHEN total_tags IS NOT NULL THEN verified_tags END"),
+            backend.double(
+                "CASE WHEN total_tags > 0 THEN verified_tags * 100.0 / total_tags WHEN total_tags = 0 THEN 0 END"
+            ),
+            backend.bigint("achievement_points"),
+            backend.bigint(words),
+            backend.bigint("bio_chars"),
+            backend.bigint(sentences),
+            backend.double(&format!(
+                "CASE WHEN ({s}) > 0 THEN ({w}) * 1.0 / ({s}) WHEN bio IS NOT NULL THEN 0 END",
+                s = sentences,
+                w = words
+            )),
+            extracted
+        );
+        let sql = backend.sql(&sql);
+        let mut phases = FetchPhases::default();
+
+        let aggregates = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| sqlx::query(&sql).bind(limit), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let aggregates: Vec<ComplexAggregate> = rows
+                .iter()
+                .map(|row| ComplexAggregate {
+                    id: row.get("id"),
+                    engagement_rate: row.get("engagement_rate"),
+                    total_tags: row.get("total_tags"),
+                    verified_tags: row.get("verified_tags"),
+                    verification_rate: row.get("verification_rate"),
+                    achievement_points: row.get("achievement_points"),
+                    word_count: row.get("word_count"),
+                    char_count: row.get("char_count"),
+                    sentence_count: row.get("sentence_count"),
+                    avg_words_per_sentence: row.get("avg_words_per_sentence"),
+                })
+                .collect();
+            phases.decode = start.elapsed();
+            aggregates
+        });
+
+        Ok((aggregates, phases))
+    }
+
+    /// Select of the documents matching `filter`, with the filter to bind.
+    fn documents_query(&self, filter: UserFilter) -> (String, UserFilter) {
+        let filter = match filter {
+            UserFilter::Email(email) => UserFilter::Email(self.email_param(email)),
+            filter => filter,
+        };
+        let backend = self.db.backend();
+        let sql = format!(
+            r#"
+            SELECT {}
+            FROM {}
+            {}
+            "#,
+            backend.json_text("data"),
+            self.layout.table(),
+            filter.clause(self.email_predicate(), self.age_expr())
+        );
+        (backend.sql(&sql).into_owned(), filter)
+    }
+
+    async fn select_documents(&self, filter: UserFilter) -> anyhow::Result<(Vec<String>, FetchPhases)> {
+        let (sql, filter) = self.documents_query(filter);
+        let mut phases = FetchPhases::default();
+
+        let documents = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| filter.clone().bind(sqlx::query(&sql)), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let documents: Vec<String> = rows.iter().map(|row| row.get("data")).collect();
+            phases.decode = start.elapsed();
+            documents
+        });
+
+        Ok((documents, phases))
+    }
+
+    /// Streaming `select_documents`: hands each document to `on_document`
+    /// as it arrives. Sets every phase but `parse`.
+    async fn select_documents_streaming(
+        &self,
+        filter: UserFilter,
+        mut on_document: impl FnMut(String) -> anyhow::Result<()> + Send,
+    ) -> anyhow::Result<FetchPhases> {
+        let (sql, filter) = self.documents_query(filter);
+        let mut phases = FetchPhases::default();
+        let mut decode = Duration::ZERO;
+
+        with_pool!(&self.db, pool => {
+            fetch_each_timed(|| filter.clone().bind(sqlx::query(&sql)), pool, &mut phases, |row| {
+                let start = Instant::now();
+                let document: String = row.get("data");
+                decode += start.elapsed();
+                on_document(document)
+            })
+            .await?;
+        });
+
+        phases.decode = decode;
+        Ok(phases)
+    }
+}
+
+/// Per-document results of `JsonStore::aggregate_complex`, the same values
+/// `benchmark::complex_processing` adds to each document.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplexAggregate {
+    pub id: String,
+    pub engagement_rate: Option<f64>,
+    pub total_tags: Option<i64>,
+    pub verified_tags: Option<i64>,
+    pub verification_rate: Option<f64>,
+    pub achievement_points: Option<i64>,
+    pub word_count: Option<i64>,
+    pub char_count: Option<i64>,
+    pub sentence_count: Option<i64>,
+    pub avg_words_per_sentence: Option<f64>,
+}
+
+#[async_trait]
+impl UserStore for JsonStore {
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn db(&self) -> &Db {
+        &self.db
+    }
+
+    fn storage_type(&self) -> String {
+        self.label(self.layout.as_str())
+    }
+
+    async fn insert(&self, user: &User) -> anyhow::Result<()> {
+        let data = serde_json::to_string(user)?;
+        let backend = self.db.backend();
+        let sql = format!(
+            "INSERT INTO {} (id, data, created_at) VALUES (?, {}, ?)",
+            self.layout.table(),
+            backend.json_param(self.json_type())
+        );
+        let sql = backend.sql(&sql);
+
+        with_pool!(&self.db, pool => {
+            sqlx::query(&sql)
+            .bind(user.id.to_string())
+            .bind(&data)
+            .bind(Timestamp(user.created_at))
+            .execute(pool)
+            .await?;
+        });
+
+        Ok(())
+    }
+
+    async fn insert_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| Ok((user, serde_json::to_string(user)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let backend = self.db.backend();
+        let row = format!("(?, {}, ?)", backend.json_param(self.json_type()));
+        let insert_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO {} (id, data, created_at) VALUES {}",
+                self.layout.table(),
+                values_list(&row, rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, insert_sql, |query, chunk| {
+            for (user, data) in chunk {
+                query = query.bind(user.id.to_string()).bind(data).bind(Timestamp(user.created_at));
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn update_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| Ok((user, serde_json::to_string(user)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let backend = self.db.backend();
+        let when = format!("WHEN ? THEN {}", backend.json_param(self.json_type()));
+        let update_sql = |rows: usize| {
+            let sql = format!(
+                "UPDATE {} SET data = CASE id {} END WHERE id IN ({})",
+                self.layout.table(),
+                vec![when.as_str(); rows].join(" "),
+                values_list(backend.id_param(), rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, update_sql, |query, chunk| {
+            for (user, data) in chunk {
+                query = query.bind(user.id.to_string()).bind(data);
+            }
+            for (user, _) in chunk {
+                query = query.bind(user.id.to_string());
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn delete_rows(&self, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        delete_by_id(&self.db, self.layout.table(), ids, mode, batch_size).await
+    }
+
+    async fn fetch(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        let started = Instant::now();
+        let mut first_parsed = None;
+
+        // Documents that are not `User`-shaped (e.g. complex profiles) are
+        // skipped and counted
+        let mut skipped = 0;
+        if self.fetch == FetchMode::Streaming {
+            let mut users = Vec::new();
+            let mut parse = Duration::ZERO;
+            let mut phases = self
+                .select_documents_streaming(filter, |document| {
+                    let start = Instant::now();
+                    match self.parser.parse(document) {
+                        Ok(user) => {
+                            users.push(user);
+                            first_parsed.get_or_insert_with(|| started.elapsed());
+                        }
+                        Err(_) => skipped += 1,
+                    }
+                    parse += start.elapsed();
+                    Ok(())
+                })
+                .await?;
+            phases.parse = parse;
+            phases.first_parsed = first_parsed;
+            phases.skipped = skipped;
+            return Ok((users, phases));
+        }
+
+        let (documents, mut phases) = self.select_documents(filter).await?;
+
+        let start = Instant::now();
+        let users = documents
+            .into_iter()
+            .filter_map(|data_str| self.parser.parse(data_str).map_err(|_| skipped += 1).ok())
+            .inspect(|_| {
+                first_parsed.get_or_insert_with(|| started.elapsed());
+            })
+            .collect();
+        phases.parse = start.elapsed();
+        phases.first_parsed = first_parsed;
+        phases.skipped = skipped;
+
+        Ok((users, phases))
+    }
+
+    async fn age_histogram(&self) -> anyhow::Result<AgeHistogram> {
+        let sql = age_histogram_sql(self.db.backend(), self.layout.table(), self.age_expr());
+
+        let histogram = with_pool!(&self.db, pool => {
+            explain::record(pool, || sqlx::query(&sql)).await?;
+            sqlx::query_as(&sql)
+                .fetch_all(pool)
+                .await?
+        });
+        Ok(histogram)
+    }
+
+    async fn count(&self) -> anyhow::Result<i64> {
+        let sql = format!("SELECT COUNT(*) FROM {}", self.layout.table());
+        let count = with_pool!(&self.db, pool => {
+            sqlx::query_scalar(&sql)
+                .fetch_one(pool)
+                .await?
+        });
+        Ok(count)
+    }
+
+    async fn stored_bytes(&self) -> anyhow::Result<i64> {
+        stored_bytes(&self.db, self.layout.table(), "id, data, created_at").await
+    }
+
+    async fn clear(&self) -> anyhow::Result<()> {
+        let sql = format!("DELETE FROM {}", self.layout.table());
+        with_pool!(&self.db, pool => {
+            sqlx::query(&sql)
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DocumentStore for JsonStore {
+    fn parser(&self) -> JsonParser {
+        self.parser
+    }
+
+    fn label(&self, name: &str) -> String {
+        self.fetch.label(&self.parser.label(name))
+    }
+
+    async fn insert_documents(&self, documents: &[(String, serde_json::Value)], batch_size: usize) -> anyhow::Result<()> {
+        // Bound like the users' created_at, so that both sort together
+        let created_at = Timestamp(OffsetDateTime::now_utc());
+        let rows: Vec<(&String, String)> = documents
+            .iter()
+            .map(|(id, document)| (id, document.to_string()))
+            .collect();
+        let backend = self.db.backend();
+        let row = format!("(?, {}, ?)", backend.json_param(self.json_type()));
+        let insert_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO {} (id, data, created_at) VALUES {}",
+                self.layout.table(),
+                values_list(&row, rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let mode = WriteMode::Batched;
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, insert_sql, |query, chunk| {
+            for (id, data) in chunk {
+                query = query.bind(*id).bind(data).bind(created_at);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn fetch_documents(&self, limit: i32) -> anyhow::Result<(Vec<String>, FetchPhases)> {
+        self.select_documents(UserFilter::Latest { limit }).await
+    }
+
+    fn fetch_mode(&self) -> FetchMode {
+        self.fetch
+    }
+
+    async fn stream_documents(
+        &self,
+        limit: i32,
+        on_document: &mut (dyn FnMut(String) -> anyhow::Result<()> + Send),
+    ) -> anyhow::Result<FetchPhases> {
+        if self.fetch == FetchMode::Buffered {
+            let (documents, phases) = self.fetch_documents(limit).await?;
+            for document in documents {
+                on_document(document)?;
+            }
+            return Ok(phases);
+        }
+        self.select_documents_streaming(UserFilter::Latest { limit }, on_document).await
+    }
+}
+
+/// Encoding of the `User` document in a BLOB layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobFormat {
+    /// `rmp-serde` with named fields, so the document keeps its keys as in
+    /// JSON.
+    MessagePack,
+    /// `ciborium`, also keyed by field name.
+    Cbor,
+    /// `bincode`: fields in declaration order, no keys.
+    Bincode,
+    /// The JSON text of `users_json`, compressed with zstd.
+    JsonZstd,
+    /// The JSON text of `users_json`, compressed with LZ4.
+    JsonLz4,
+}
+
+impl BlobFormat {
+    fn layout(&self) -> Layout {
+        match self {
+            BlobFormat::MessagePack => Layout::MessagePack,
+            BlobFormat::Cbor => Layout::Cbor,
+            BlobFormat::Bincode => Layout::Bincode,
+            BlobFormat::JsonZstd => Layout::JsonZstd,
+            BlobFormat::JsonLz4 => Layout::JsonLz4,
+        }
+    }
+
+    fn compression(&self) -> Compression {
+        match self {
+            BlobFormat::MessagePack | BlobFormat::Cbor | BlobFormat::Bincode => Compression::None,
+            BlobFormat::JsonZstd => Compression::Zstd,
+            BlobFormat::JsonLz4 => Compression::Lz4,
+        }
+    }
+
+    fn is_json(&self) -> bool {
+        matches!(self, BlobFormat::JsonZstd | BlobFormat::JsonLz4)
+    }
+
+    fn encode(&self, user: &User) -> anyhow::Result<Vec<u8>> {
+        let data = match self {
+            BlobFormat::MessagePack => rmp_serde::to_vec_named(user)?,
+            BlobFormat::Cbor => {
+                let mut data = Vec::new();
+                ciborium::into_writer(user, &mut data)?;
+                data
+            }
+            BlobFormat::Bincode => bincode::serialize(user)?,
+            BlobFormat::JsonZstd | BlobFormat::JsonLz4 => serde_json::to_vec(user)?,
+        };
+        self.compression().compress(data)
+    }
+
+    /// Deserializes decompressed `data`, parsing JSON with `parser`.
+    fn decode(&self, data: Vec<u8>, parser: JsonParser) -> anyhow::Result<User> {
+        Ok(match self {
+            BlobFormat::MessagePack => rmp_serde::from_slice(&data)?,
+            BlobFormat::Cbor => ciborium::from_reader(data.as_slice())?,
+            BlobFormat::Bincode => bincode::deserialize(&data)?,
+            BlobFormat::JsonZstd | BlobFormat::JsonLz4 => parser.parse(String::from_utf8(data)?)?,
+        })
+    }
+}
+
+/// `users_msgpack`, `users_cbor` and `users_bincode`: the `users_json`
+/// document in a binary encoding. `users_json_zstd` and `users_json_lz4`
+/// store its JSON text compressed by the application. The database cannot
+/// look inside a BLOB, so email and age filters decode every row and match in
+/// the application.
+pub struct BlobStore {
+    db: Db,
+    format: BlobFormat,
+    parser: JsonParser,
+}
+
+impl BlobStore {
+    pub fn new(db: Db, format: BlobFormat) -> Self {
+        Self {
+            db,
+            format,
+            parser: JsonParser::default(),
+        }
+    }
+
+    /// Parses the JSON formats with `parser` instead of serde_json.
+    pub fn with_parser(self, parser: JsonParser) -> Self {
+        Self { parser, ..self }
+    }
+
+    fn table(&self) -> &'static str {
+        self.format.layout().table()
+    }
+
+    /// Decompressed `data` of the newest `limit` rows, or of every row.
+    async fn select_data(&self, limit: Option<i32>) -> anyhow::Result<(Vec<Vec<u8>>, FetchPhases)> {
+        let sql = match limit {
+            Some(_) => format!("SELECT data FROM {} ORDER BY created_at DESC LIMIT ?", self.table()),
+            None => format!("SELECT data FROM {}", self.table()),
+        };
+        let sql = self.db.backend().sql(&sql);
+        let mut phases = FetchPhases::default();
+
+        let data: Vec<Vec<u8>> = with_pool!(&self.db, pool => {
+            let query = || {
+                let mut query = sqlx::query(&sql);
+                if let Some(limit) = limit {
+                    query = query.bind(limit);
+                }
+                query
+            };
+            let rows = fetch_all_timed(query, pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let data = rows.iter().map(|row| row.get("data")).collect();
+            phases.decode = start.elapsed();
+            data
+        });
+
+        let start = Instant::now();
+        let compression = self.format.compression();
+        let data = data
+            .into_iter()
+            .map(|data| compression.decompress(data))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        phases.decompress = start.elapsed();
+
+        Ok((data, phases))
+    }
+
+    /// `data` deserialized, and how many rows were skipped as not
+    /// `User`-shaped (e.g. complex profiles).
+    fn decode_users(&self, data: Vec<Vec<u8>>) -> (Vec<User>, usize) {
+        let mut skipped = 0;
+        let users = data
+            .into_iter()
+            .filter_map(|data| self.format.decode(data, self.parser).map_err(|_| skipped += 1).ok())
+            .collect();
+        (users, skipped)
+    }
+}
+
+#[async_trait]
+impl UserStore for BlobStore {
+    fn layout(&self) -> Layout {
+        self.format.layout()
+    }
+
+    fn db(&self) -> &Db {
+        &self.db
+    }
+
+    fn storage_type(&self) -> String {
+        if self.format.is_json() {
+            self.parser.label(self.layout().as_str())
+        } else {
+            self.layout().as_str().to_string()
+        }
+    }
+
+    async fn insert(&self, user: &User) -> anyhow::Result<()> {
+        self.insert_rows(std::slice::from_ref(user), WriteMode::Single, 1).await
+    }
+
+    async fn insert_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| Ok((user, self.format.encode(user)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let backend = self.db.backend();
+        let insert_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO {} (id, data, created_at) VALUES {}",
+                self.table(),
+                values_list("(?, ?, ?)", rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, insert_sql, |query, chunk| {
+            for (user, data) in chunk {
+                query = query.bind(user.id.to_string()).bind(data).bind(Timestamp(user.created_at));
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn update_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| Ok((user, self.format.encode(user)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let backend = self.db.backend();
+        let update_sql = |rows: usize| {
+            let sql = format!(
+                "UPDATE {} SET data = CASE id {} END WHERE id IN ({})",
+                self.table(),
+                vec!["WHEN ? THEN ?"; rows].join(" "),
+                values_list(backend.id_param(), rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, update_sql, |query, chunk| {
+            for (user, data) in chunk {
+                query = query.bind(user.id.to_string()).bind(data);
+            }
+            for (user, _) in chunk {
+                query = query.bind(user.id.to_string());
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn delete_rows(&self, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        delete_by_id(&self.db, self.table(), ids, mode, batch_size).await
+    }
+
+    async fn fetch(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        // Only `Latest` can be answered by the database
+        let limit = match filter {
+            UserFilter::Latest { limit } => Some(limit),
+            UserFilter::Email(_) | UserFilter::AgeRange { .. } => None,
+        };
+        let (data, mut phases) = self.select_data(limit).await?;
+
+        let start = Instant::now();
+        let (users, skipped) = self.decode_users(data);
+        let users = match filter {
+            UserFilter::Latest { .. } => users,
+            UserFilter::Email(email) => users.into_iter().filter(|user| user.email == email).collect(),
+            UserFilter::AgeRange { min, max, limit } => users
+                .into_iter()
+                .filter(|user| (min..=max).contains(&user.age))
+                .take(limit.max(0) as usize)
+                .collect(),
+        };
+        phases.parse = start.elapsed();
+        phases.skipped = skipped;
+
+        Ok((users, phases))
+    }
+
+    async fn age_histogram(&self) -> anyhow::Result<AgeHistogram> {
+        let (data, _) = self.select_data(None).await?;
+
+        // Unlike the SQL histograms, rows that are not users are not counted
+        let mut histogram = BTreeMap::new();
+        for user in self.decode_users(data).0 {
+            *histogram.entry(i64::from(user.age)).or_insert(0) += 1;
+        }
+        Ok(histogram.into_iter().collect())
+    }
+
+    async fn count(&self) -> anyhow::Result<i64> {
+        let sql = format!("SELECT COUNT(*) FROM {}", self.table());
+        let count = with_pool!(&self.db, pool => {
+            sqlx::query_scalar(&sql)
+                .fetch_one(pool)
+                .await?
+        });
+        Ok(count)
+    }
+
+    async fn stored_bytes(&self) -> anyhow::Result<i64> {
+        stored_bytes(&self.db, self.table(), "id, data, created_at").await
+    }
+
+    async fn clear(&self) -> anyhow::Result<()> {
+        let sql = format!("DELETE FROM {}", self.table());
+        with_pool!(&self.db, pool => {
+            sqlx::query(&sql)
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+}
+
+/// Only the compressed JSON formats hold documents.
+#[async_trait]
+impl DocumentStore for BlobStore {
+    fn parser(&self) -> JsonParser {
+        self.parser
+    }
+
+    fn label(&self, name: &str) -> String {
+        self.format.compression().label(&self.parser.label(name))
+    }
+
+    async fn insert_documents(&self, documents: &[(String, serde_json::Value)], batch_size: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(self.format.is_json(), "{} does not store JSON documents", self.table());
+        let compression = self.format.compression();
+        let rows = documents
+            .iter()
+            .map(|(id, document)| Ok((id, compression.compress(document.to_string().into_bytes())?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let created_at = Timestamp(OffsetDateTime::now_utc());
+        let backend = self.db.backend();
+        let insert_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO {} (id, data, created_at) VALUES {}",
+                self.table(),
+                values_list("(?, ?, ?)", rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let mode = WriteMode::Batched;
+        let per_statement = mode.rows_per_statement(batch_size, 3);
+
+        execute_rows!(&self.db, mode, rows, per_statement, insert_sql, |query, chunk| {
+            for (id, data) in chunk {
+                query = query.bind(*id).bind(data).bind(created_at);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn fetch_documents(&self, limit: i32) -> anyhow::Result<(Vec<String>, FetchPhases)> {
+        anyhow::ensure!(self.format.is_json(), "{} does not store JSON documents", self.table());
+        let (data, mut phases) = self.select_data(Some(limit)).await?;
+
+        // Validating UTF-8 is part of turning the BLOB back into text
+        let start = Instant::now();
+        let documents = data
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<_>, _>>()?;
+        phases.decompress += start.elapsed();
+
+        Ok((documents, phases))
+    }
+}
+
+/// How `NormalizedStore` reassembles users from its three tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchStrategy {
+    /// One query LEFT JOINing both child tables, so each user comes back
+    /// once per preference and social link pair.
+    #[default]
+    Join,
+    /// The users first, then all of their preferences and social links in a
+    /// second query keyed by `user_id IN (...)`.
+    TwoQuery,
+}
+
+impl FetchStrategy {
+    pub const ALL: [FetchStrategy; 2] = [FetchStrategy::Join, FetchStrategy::TwoQuery];
+}
+
+/// `users_normalized`: the scalar columns of `users_column`, with each
+/// preference a `user_preferences` row and each social link a
+/// `user_social_links` row. Child rows are removed by `ON DELETE CASCADE`.
+pub struct NormalizedStore {
+    db: Db,
+    strategy: FetchStrategy,
+}
+
+impl NormalizedStore {
+    pub fn with_strategy(db: Db, strategy: FetchStrategy) -> Self {
+        Self { db, strategy }
+    }
+
+    async fn fetch_joined(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        let backend = self.db.backend();
+        // The filter limits users, not joined rows, so it goes in a derived table
+        let sql = format!(
+            r#"
+            SELECT u.id, u.name, u.email, u.age, u.bio, u.avatar_url, u.created_at,
+                   p.pref_key, p.pref_value, l.position, l.url
+            FROM (SELECT {} FROM users_normalized {}) AS u
+            LEFT JOIN user_preferences p ON p.user_id = u.id
+            LEFT JOIN user_social_links l ON l.user_id = u.id
+            ORDER BY u.created_at DESC, u.id
+            "#,
+            NORMALIZED_COLUMNS,
+            filter.clause("email = ?", "age")
+        );
+        let sql = backend.sql(&sql);
+        let mut phases = FetchPhases::default();
+
+        let decoded: Vec<JoinedRow> = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| filter.clone().bind(sqlx::query(&sql)), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let decoded = rows
+                .iter()
+                .map(|row| JoinedRow {
+                    user: NormalizedRow {
+                        id: row.get("id"),
+                        name: row.get("name"),
+                        email: row.get("email"),
+                        age: row.get("age"),
+                        bio: row.get("bio"),
+                        avatar_url: row.get("avatar_url"),
+                        created_at: row.get("created_at"),
+                    },
+                    pref_key: row.get("pref_key"),
+                    pref_value: row.get("pref_value"),
+                    position: row.get("position"),
+                    url: row.get("url"),
+                })
+                .collect();
+            phases.decode = start.elapsed();
+            decoded
+        });
+
+        // Rows of one user are adjacent; collapse the preference x link product
+        let start = Instant::now();
+        let mut grouped: Vec<(NormalizedRow, ProfileRows)> = Vec::new();
+        for row in decoded {
+            let same_user = grouped.last().is_some_and(|(user, _)| user.id == row.user.id);
+            if !same_user {
+                grouped.push((row.user, ProfileRows::default()));
+            }
+            let (_, (preferences, social_links)) = grouped.last_mut().expect("pushed above");
+            if let (Some(key), Some(value)) = (row.pref_key, row.pref_value) {
+                preferences.insert(key, value);
+            }
+            if let (Some(position), Some(url)) = (row.position, row.url) {
+                social_links.insert(position.into(), url);
+            }
+        }
+        let users = grouped
+            .into_iter()
+            .map(|(user, profile)| user.into_user(profile))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        phases.parse = start.elapsed();
+
+        Ok((users, phases))
+    }
+
+    async fn fetch_two_query(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        let backend = self.db.backend();
+        let users_sql = format!(
+            "SELECT {} FROM users_normalized {}",
+            NORMALIZED_COLUMNS,
+            filter.clause("email = ?", "age")
+        );
+        let users_sql = backend.sql(&users_sql);
+        let children_sql = |rows: usize| {
+            let ids = values_list(backend.id_param(), rows);
+            let sql = format!(
+                r#"
+                SELECT user_id, pref_key, pref_value AS value, {} AS position
+                FROM user_preferences WHERE user_id IN ({})
+                UNION ALL
+                SELECT user_id, NULL, url, {}
+                FROM user_social_links WHERE user_id IN ({})
+                "#,
+                backend.bigint("NULL"),
+                ids,
+                backend.bigint("position"),
+                ids
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let mut phases = FetchPhases::default();
+
+        let (parents, children): (Vec<NormalizedRow>, Vec<ChildRow>) = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| filter.clone().bind(sqlx::query(&users_sql)), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let parents: Vec<NormalizedRow> = rows
+                .iter()
+                .map(|row| NormalizedRow {
+                    id: row.get("id"),
+                    name: row.get("name"),
+                    email: row.get("email"),
+                    age: row.get("age"),
+                    bio: row.get("bio"),
+                    avatar_url: row.get("avatar_url"),
+                    created_at: row.get("created_at"),
+                })
+                .collect();
+            phases.decode = start.elapsed();
+
+            // Every id is bound twice, once per child table
+            let mut children = Vec::new();
+            for chunk in parents.chunks(MAX_BIND_PARAMS / 2) {
+                let sql = children_sql(chunk.len());
+                let query = || {
+                    let mut query = sqlx::query(&sql);
+                    for _ in 0..2 {
+                        for user in chunk {
+                            query = query.bind(&user.id);
+                        }
+                    }
+                    query
+                };
+                let mut child_phases = FetchPhases::default();
+                let rows = fetch_all_timed(query, pool, &mut child_phases).await?;
+
+                let start = Instant::now();
+                children.extend(rows.iter().map(|row| ChildRow {
+                    user_id: row.get("user_id"),
+                    pref_key: row.get("pref_key"),
+                    value: row.get("value"),
+                    position: row.get("position"),
+                }));
+                child_phases.decode = start.elapsed();
+                phases += child_phases;
+            }
+            (parents, children)
+        });
+
+        let start = Instant::now();
+        let mut profiles: HashMap<String, ProfileRows> = HashMap::new();
+        for child in children {
+            let (preferences, social_links) = profiles.entry(child.user_id).or_default();
+            match (child.pref_key, child.position) {
+                (Some(key), _) => {
+                    preferences.insert(key, child.value);
+                }
+                (None, Some(position)) => {
+                    social_links.insert(position, child.value);
+                }
+                (None, None) => {}
+            }
+        }
+        let users = parents
+            .into_iter()
+            .map(|user| {
+                let profile = profiles.remove(&user.id).unwrap_or_default();
+                user.into_user(profile)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        phases.parse = start.elapsed();
+
+        Ok((users, phases))
+    }
+}
+
+const NORMALIZED_COLUMNS: &str = "id, name, email, age, bio, avatar_url, created_at";
+
+/// Child rows of one user: preferences by key, social links by position.
+type ProfileRows = (HashMap<String, String>, BTreeMap<i64, String>);
+
+/// A `users_normalized` row decoded from the driver.
+struct NormalizedRow {
+    id: String,
+    name: String,
+    email: String,
+    age: i32,
+    bio: String,
+    avatar_url: Option<String>,
+    created_at: OffsetDateTime,
+}
+
+impl NormalizedRow {
+    fn into_user(self, (preferences, social_links): ProfileRows) -> anyhow::Result<User> {
+        Ok(User {
+            id: Uuid::parse_str(&self.id)?,
+            name: self.name,
+            email: self.email,
+            age: self.age,
+            profile: UserProfile {
+                bio: self.bio,
+                avatar_url: self.avatar_url,
+                preferences,
+                social_links: social_links.into_values().collect(),
+            },
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// One row of the `FetchStrategy::Join` result: a user with at most one
+/// preference and one social link.
+struct JoinedRow {
+    user: NormalizedRow,
+    pref_key: Option<String>,
+    pref_value: Option<String>,
+    position: Option<i32>,
+    url: Option<String>,
+}
+
+/// A preference (`pref_key` set) or social link (`position` set) from the
+/// second `FetchStrategy::TwoQuery` query.
+struct ChildRow {
+    user_id: String,
+    pref_key: Option<String>,
+    value: String,
+    position: Option<i64>,
+}
+
+#[async_trait]
+impl UserStore for NormalizedStore {
+    fn layout(&self) -> Layout {
+        Layout::Normalized
+    }
+
+    fn db(&self) -> &Db {
+        &self.db
+    }
+
+    fn storage_type(&self) -> String {
+        match self.strategy {
+            FetchStrategy::Join => "normalized".to_string(),
+            FetchStrategy::TwoQuery => "normalized_two_query".to_string(),
+        }
+    }
+
+    async fn insert(&self, user: &User) -> anyhow::Result<()> {
+        // One statement per table, in one transaction
+        self.insert_rows(std::slice::from_ref(user), WriteMode::Batched, MAX_BIND_PARAMS).await
+    }
+
+    async fn insert_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        let preferences: Vec<(String, &String, &String)> = users
+            .iter()
+            .flat_map(|user| {
+                let id = user.id.to_string();
+                user.profile.preferences.iter().map(move |(key, value)| (id.clone(), key, value))
+            })
+            .collect();
+        let social_links: Vec<(String, i32, &String)> = users
+            .iter()
+            .flat_map(|user| {
+                let id = user.id.to_string();
+                (0..).zip(&user.profile.social_links).map(move |(position, url)| (id.clone(), position, url))
+            })
+            .collect();
+        let backend = self.db.backend();
+        let insert_sql = |table: &'static str, columns: &'static str, row: &'static str| {
+            move |rows: usize| {
+                let sql = format!("INSERT INTO {} ({}) VALUES {}", table, columns, values_list(row, rows));
+                backend.sql(&sql).into_owned()
+            }
+        };
+        let users_sql = insert_sql("users_normalized", NORMALIZED_COLUMNS, "(?, ?, ?, ?, ?, ?, ?)");
+        let preferences_sql = insert_sql("user_preferences", "user_id, pref_key, pref_value", "(?, ?, ?)");
+        let social_links_sql = insert_sql("user_social_links", "user_id, position, url", "(?, ?, ?)");
+
+        // Users go first so the child rows satisfy their foreign keys
+        execute_rows!(&self.db, mode,
+            users, mode.rows_per_statement(batch_size, 7), users_sql, |query, chunk| {
+                for user in chunk {
+                    query = query
+                        .bind(user.id.to_string())
+                        .bind(&user.name)
+                        .bind(&user.email)
+                        .bind(user.age)
+                        .bind(&user.profile.bio)
+                        .bind(&user.profile.avatar_url)
+                        .bind(Timestamp(user.created_at));
+                }
+            };
+            preferences, mode.rows_per_statement(batch_size, 3), preferences_sql, |query, chunk| {
+                for (user_id, key, value) in chunk {
+                    query = query.bind(user_id).bind(*key).bind(*value);
+                }
+            };
+            social_links, mode.rows_per_statement(batch_size, 3), social_links_sql, |query, chunk| {
+                for (user_id, position, url) in chunk {
+                    query = query.bind(user_id).bind(*position).bind(*url);
+                }
+            }
+        );
+
+        Ok(())
+    }
+
+    async fn update_rows(&self, users: &[User], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        update_age(&self.db, Layout::Normalized.table(), users, mode, batch_size).await
+    }
+
+    async fn delete_rows(&self, ids: &[Uuid], mode: WriteMode, batch_size: usize) -> anyhow::Result<()> {
+        delete_by_id(&self.db, Layout::Normalized.table(), ids, mode, batch_size).await
+    }
+
+    async fn fetch(&self, filter: UserFilter) -> anyhow::Result<(Vec<User>, FetchPhases)> {
+        match self.strategy {
+            FetchStrategy::Join => self.fetch_joined(filter).await,
+            FetchStrategy::TwoQuery => self.fetch_two_query(filter).await,
+        }
+    }
+
+    async fn age_histogram(&self) -> anyhow::Result<AgeHistogram> {
+        let sql = age_histogram_sql(self.db.backend(), Layout::Normalized.table(), "age");
+
+        let histogram = with_pool!(&self.db, pool => {
+            explain::record(pool, || sqlx::query(&sql)).await?;
+            sqlx::query_as(&sql)
+                .fetch_all(pool)
+                .await?
+        });
+        Ok(histogram)
+    }
+
+    async fn count(&self) -> anyhow::Result<i64> {
+        let count = with_pool!(&self.db, pool => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM users_normalized")
+                .fetch_one(pool)
+                .await?
+        });
+        Ok(count)
+    }
+
+    async fn stored_bytes(&self) -> anyhow::Result<i64> {
+        let users = stored_bytes(&self.db, Layout::Normalized.table(), NORMALIZED_COLUMNS).await?;
+        let preferences = stored_bytes(&self.db, "user_preferences", "user_id, pref_key, pref_value").await?;
+        let social_links = stored_bytes(&self.db, "user_social_links", "user_id, position, url").await?;
+        Ok(users + preferences + social_links)
+    }
+
+    async fn clear(&self) -> anyhow::Result<()> {
+        with_pool!(&self.db, pool => {
+            sqlx::query("DELETE FROM users_normalized")
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+}
+
+/// `complex_column`: the complex profile of `users_json` with statistics and
+/// metadata as columns, achievements in `complex_achievements` and tags in
+/// `complex_tags`. Preferences and social links stay JSON as in
+/// `users_column`.
+pub struct ComplexColumnStore {
+    db: Db,
+}
+
+impl ComplexColumnStore {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    /// Inserts `users` with their achievements and tags in one transaction,
+    /// `batch_size` rows per INSERT.
+    pub async fn insert_users(&self, users: &[ComplexUser], batch_size: usize) -> anyhow::Result<()> {
+        let rows = users
+            .iter()
+            .map(|user| {
+                Ok((
+                    user,
+                    serde_json::to_string(&user.profile.preferences)?,
+                    serde_json::to_string(&user.profile.social_links)?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let achievements: Vec<(String, i32, &Achievement)> = users
+            .iter()
+            .flat_map(|user| {
+                let id = user.id.to_string();
+                (0..).zip(&user.profile.achievements).map(move |(position, achievement)| (id.clone(), position, achievement))
+            })
+            .collect();
+        let tags: Vec<(String, i32, &String)> = users
+            .iter()
+            .flat_map(|user| {
+                let id = user.id.to_string();
+                (0..).zip(&user.metadata.tags).map(move |(position, tag)| (id.clone(), position, tag))
+            })
+            .collect();
+        let backend = self.db.backend();
+        let json = backend.json_param("JSONB");
+        let user_row = format!("(?, ?, ?, ?, ?, ?, {}, {}, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", json, json);
+        let users_sql = |rows: usize| {
+            let sql = format!("INSERT INTO complex_column ({}) VALUES {}", COMPLEX_COLUMNS, values_list(&user_row, rows));
+            backend.sql(&sql).into_owned()
+        };
+        let achievements_sql = |rows: usize| {
+            let sql = format!(
+                "INSERT INTO complex_achievements ({}) VALUES {}",
+                ACHIEVEMENT_COLUMNS,
+                values_list("(?, ?, ?, ?, ?, ?, ?)", rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let tags_sql = |rows: usize| {
+            let sql = format!("INSERT INTO complex_tags (user_id, position, tag) VALUES {}", values_list("(?, ?, ?)", rows));
+            backend.sql(&sql).into_owned()
+        };
+        let mode = WriteMode::Batched;
+
+        execute_rows!(&self.db, mode,
+            rows, mode.rows_per_statement(batch_size, 18), users_sql, |query, chunk| {
+                for (user, preferences, social_links) in chunk {
+                    let stats = &user.profile.statistics;
+                    query = query
+                        .bind(user.id.to_string())
+                        .bind(&user.name)
+                        .bind(&user.email)
+                        .bind(user.age)
+                        .bind(&user.profile.bio)
+                        .bind(&user.profile.avatar_url)
+                        .bind(preferences)
+                        .bind(social_links)
+                        .bind(stats.posts_count as i64)
+                        .bind(stats.followers_count as i64)
+                        .bind(stats.following_count as i64)
+                        .bind(stats.likes_received as i64)
+                        .bind(stats.comments_made as i64)
+                        .bind(Timestamp(user.metadata.created_at))
+                        .bind(Timestamp(user.metadata.last_login))
+                        .bind(user.metadata.login_count as i64)
+                        .bind(user.metadata.is_verified)
+                        .bind(user.metadata.is_premium);
+                }
+            };
+            achievements, mode.rows_per_statement(batch_size, 7), achievements_sql, |query, chunk| {
+                for (user_id, position, achievement) in chunk {
+                    query = query
+                        .bind(user_id)
+                        .bind(*position)
+                        .bind(&achievement.id)
+                        .bind(&achievement.name)
+                        .bind(&achievement.description)
+                        .bind(Timestamp(achievement.earned_at))
+                        .bind(achievement.points as i64);
+                }
+            };
+            tags, mode.rows_per_statement(batch_size, 3), tags_sql, |query, chunk| {
+                for (user_id, position, tag) in chunk {
+                    query = query.bind(user_id).bind(*position).bind(*tag);
+                }
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Newest `limit` profiles rebuilt into `ComplexUser`: the profiles first,
+    /// then their achievements and tags keyed by `user_id IN (...)`.
+    /// Rebuilding counts as the parse phase.
+    pub async fn fetch_users(&self, limit: i32) -> anyhow::Result<(Vec<ComplexUser>, FetchPhases)> {
+        let backend = self.db.backend();
+        let users_sql = format!(
+            "SELECT id, name, email, age, bio, avatar_url, {}, {}, posts_count, followers_count, following_count, \
+             likes_received, comments_made, metadata_created_at, last_login, login_count, is_verified, is_premium \
+             FROM complex_column ORDER BY created_at DESC LIMIT ?",
+            backend.json_text("preferences"),
+            backend.json_text("social_links")
+        );
+        let users_sql = backend.sql(&users_sql);
+        let child_sql = |columns: &str, table: &str, rows: usize| {
+            let sql = format!(
+                "SELECT {} FROM {} WHERE user_id IN ({})",
+                columns,
+                table,
+                values_list(backend.id_param(), rows)
+            );
+            backend.sql(&sql).into_owned()
+        };
+        let mut phases = FetchPhases::default();
+
+        let (decoded, achievements, tags) = with_pool!(&self.db, pool => {
+            let rows = fetch_all_timed(|| sqlx::query(&users_sql).bind(limit), pool, &mut phases).await?;
+
+            let start = Instant::now();
+            let decoded: Vec<ComplexColumnRow> = rows
+                .iter()
+                .map(|row| ComplexColumnRow {
+                    id: row.get("id"),
+                    name: row.get("name"),
+                    email: row.get("email"),
+                    age: row.get("age"),
+                    bio: row.get("bio"),
+                    avatar_url: row.get("avatar_url"),
+                    preferences: row.get("preferences"),
+                    social_links: row.get("social_links"),
+                    statistics: [
+                        row.get("posts_count"),
+                        row.get("followers_count"),
+                        row.get("following_count"),
+                        row.get("likes_received"),
+                        row.get("comments_made"),
+                    ],
+                    metadata_created_at: row.get("metadata_created_at"),
+                    last_login: row.get("last_login"),
+                    login_count: row.get("login_count"),
+                    is_verified: row.get("is_verified"),
+                    is_premium: row.get("is_premium"),
+                })
+                .collect();
+            phases.decode = start.elapsed();
+
+            let mut achievements: Vec<(String, i32, Achievement)> = Vec::new();
+            let mut tags: Vec<(String, i32, String)> = Vec::new();
+            for chunk in decoded.chunks(MAX_BIND_PARAMS) {
+                let sql = child_sql(ACHIEVEMENT_COLUMNS, "complex_achievements", chunk.len());
+                let query = || {
+                    let mut query = sqlx::query(&sql);
+                    for user in chunk {
+                        query = query.bind(&user.id);
+                    }
+                    query
+                };
+                let mut child_phases = FetchPhases::default();
+                let rows = fetch_all_timed(query, pool, &mut child_phases).await?;
+                let start = Instant::now();
+                achievements.extend(rows.iter().map(|row| {
+                    let achievement = Achievement {
+                        id: row.get("achievement_id"),
+                        name: row.get("name"),
+                        description: row.get("description"),
+                        earned_at: row.get("earned_at"),
+                        points: row.get::<i64, _>("points") as u64,
+                    };
+                    (row.get("user_id"), row.get("position"), achievement)
+                }));
+                child_phases.decode = start.elapsed();
+                phases += child_phases;
+
+                let sql = child_sql("user_id, position, tag", "complex_tags", chunk.len());
+                let query = || {
+                    let mut query = sqlx::query(&sql);
+                    for user in chunk {
+                        query = query.bind(&user.id);
+                    }
+                    query
+                };
+                let mut child_phases = FetchPhases::default();
+                let rows = fetch_all_timed(query, pool, &mut child_phases).await?;
+                let start = Instant::now();
+                tags.extend(rows.iter().map(|row| (row.get("user_id"), row.get("position"), row.get("tag"))));
+                child_phases.decode = start.elapsed();
+                phases += child_phases;
+            }
+            (decoded, achievements, tags)
+        });
+
+        let start = Instant::now();
+        let mut children: HashMap<String, ComplexChildren> = HashMap::new();
+        for (user_id, position, achievement) in achievements {
+            children.entry(user_id).or_default().0.insert(position, achievement);
+        }
+        for (user_id, position, tag) in tags {
+            children.entry(user_id).or_default().1.insert(position, tag);
+        }
+        let users = decoded
+            .into_iter()
+            .map(|row| {
+                let child_rows = children.remove(&row.id).unwrap_or_default();
+                row.into_user(child_rows)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        phases.parse = start.elapsed();
+
+        Ok((users, phases))
+    }
+
+    pub async fn count(&self) -> anyhow::Result<i64> {
+        let count = with_pool!(&self.db, pool => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM complex_column")
+                .fetch_one(pool)
+                .await?
+        });
+        Ok(count)
+    }
+
+    pub async fn clear(&self) -> anyhow::Result<()> {
+        with_pool!(&self.db, pool => {
+            sqlx::query("DELETE FROM complex_column")
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+}
+
+const COMPLEX_COLUMNS: &str = "id, name, email, age, bio, avatar_url, preferences, social_links, \
+    posts_count, followers_count, following_count, likes_received, comments_made, \
+    metadata_created_at, last_login, login_count, is_verified, is_premium";
+
+const ACHIEVEMENT_COLUMNS: &str = "user_id, position, achievement_id, name, description, earned_at, points";
+
+/// Achievements and tags of one profile, by position.
+type ComplexChildren = (BTreeMap<i32, Achievement>, BTreeMap<i32, String>);
+
+/// A `complex_column` row decoded from the driver, JSON columns still as text.
+struct ComplexColumnRow {
+    id: String,
+    name: String,
+    email: String,
+    age: i32,
+    bio: String,
+    avatar_url: Option<String>,
+    preferences: String,
+    social_links: String,
+    /// posts, followers, following, likes received, comments made
+    statistics: [i64; 5],
+    metadata_created_at: OffsetDateTime,
+    last_login: OffsetDateTime,
+    login_count: i64,
+    is_verified: bool,
+    is_premium: bool,
+}
+
+impl ComplexColumnRow {
+    fn into_user(self, (achievements, tags): ComplexChildren) -> anyhow::Result<ComplexUser> {
+        let preferences: ComplexPreferences = serde_json::from_str(&self.preferences)?;
+        let social_links: Vec<String> = serde_json::from_str(&self.social_links)?;
+        let [posts_count, followers_count, following_count, likes_received, comments_made] =
+            self.statistics.map(|value| value as u64);
+
+        Ok(ComplexUser {
+            id: Uuid::parse_str(&self.id)?,
+            name: self.name,
+            email: self.email,
+            age: self.age,
+            profile: ComplexProfile {
+                bio: self.bio,
+                avatar_url: self.avatar_url,
+                preferences,
+                social_links,
+                achievements: achievements.into_values().collect(),
+                statistics: Statistics {
+                    posts_count,
+                    followers_count,
+                    following_count,
+                    likes_received,
+                    comments_made,
+                    engagement_rate: None,
+                },
+                total_achievement_points: None,
+                bio_analysis: None,
+            },
+            metadata: Metadata {
+                created_at: self.metadata_created_at,
+                last_login: self.last_login,
+                login_count: self.login_count as u64,
+                is_verified: self.is_verified,
+                is_premium: self.is_premium,
+                tags: tags.into_values().collect(),
+                tag_analysis: None,
+            },
+        })
+    }
+}
@@ -0,0 +1,174 @@
+//! Round trips shared by the per-backend integration tests: migrate, generate
+//! through the same code as `generate`, then read back through the stores.
+
+use structure_column_performance::benchmark::{self, ComplexModel, RunOptions};
+use structure_column_performance::compression::Compression;
+use structure_column_performance::config::{PoolConfig, WriteConfig};
+use structure_column_performance::data_generator;
+use structure_column_performance::db::Db;
+use structure_column_performance::explain::{self, ExplainMode};
+use structure_column_performance::migrate::{self, IndexStatus};
+use structure_column_performance::models::ComplexUser;
+use structure_column_performance::parser::JsonParser;
+use structure_column_performance::store::{self, ComplexColumnStore, FetchMode, JsonStore, Layout, UserFilter};
+
+/// Small batches and transactions, so that a few dozen rows already span
+/// several of each.
+const WRITE: WriteConfig = WriteConfig {
+    batch_size: 7,
+    transaction_size: 20,
+};
+
+const USERS: i32 = 50;
+const DOCUMENTS: i32 = 20;
+
+/// Index each document layout's email lookup is written for. `column` and
+/// `normalized` are left out, as their `UNIQUE` constraint serves it too.
+const EMAIL_INDEXES: [(Layout, &str); 4] = [
+    (Layout::Json, "idx_users_json_email"),
+    (Layout::Jsonb, "idx_users_jsonb_email"),
+    (Layout::JsonbGin, "idx_users_jsonb_gin_data"),
+    (Layout::Hybrid, "idx_users_hybrid_email"),
+];
+
+/// Connects to `url` and applies the migrations, which must leave every
+/// index the benchmarks expect in place.
+pub async fn connect(url: &str) -> anyhow::Result<Db> {
+    let db = Db::connect(url, &PoolConfig::default()).await?;
+    let report = migrate::run(&db).await?;
+    for index in &report.indexes {
+        assert!(
+            !matches!(index.status, IndexStatus::Missing),
+            "{}.{} missing after migrating",
+            index.table,
+            index.index
+        );
+    }
+    migrate::ensure_up_to_date(&db).await?;
+    Ok(db)
+}
+
+/// Every layout of the backend stores the generated users and reads them back
+/// newest first, by email and by age range.
+pub async fn layouts_round_trip(db: &Db) -> anyhow::Result<()> {
+    for layout in Layout::ALL.into_iter().filter(|layout| layout.is_supported_by(db.backend())) {
+        let store = store::for_layout(layout, db.clone())?;
+        store.clear().await?;
+
+        data_generator::generate_users(store.as_ref(), USERS, &WRITE).await?;
+        assert_eq!(store.count().await?, i64::from(USERS), "{} row count", layout.as_str());
+
+        let mut latest: Vec<String> = store.fetch_latest(10).await?.into_iter().map(|user| user.email).collect();
+        latest.sort();
+        let mut expected: Vec<String> = (USERS - 9..=USERS).map(|i| format!("user{}@example.com", i)).collect();
+        expected.sort();
+        assert_eq!(latest, expected, "{} newest users", layout.as_str());
+
+        let (found, _) = store.fetch(UserFilter::Email("user7@example.com".to_string())).await?;
+        let expected = data_generator::simple_user(7);
+        assert_eq!(found.len(), 1, "{} email lookup", layout.as_str());
+        let user = &found[0];
+        assert_eq!(
+            (&user.name, user.age, &user.profile.bio, &user.profile.avatar_url),
+            (&expected.name, expected.age, &expected.profile.bio, &expected.profile.avatar_url),
+            "{} user fields",
+            layout.as_str()
+        );
+        assert_eq!(user.profile.preferences, expected.profile.preferences, "{} preferences", layout.as_str());
+        assert_eq!(user.profile.social_links, expected.profile.social_links, "{} social links", layout.as_str());
+
+        // Ages are 20 + i % 60, so users 10..=19 are in their thirties
+        let (thirties, _) = store.fetch(UserFilter::AgeRange { min: 30, max: 39, limit: 100 }).await?;
+        assert_eq!(thirties.len(), 10, "{} age range", layout.as_str());
+
+        for read in store::ReadOptions::variants(layout) {
+            let store = store::for_layout_with(layout, db.clone(), read)?;
+            assert_eq!(store.fetch_latest(USERS).await?.len(), USERS as usize, "{}", store.storage_type());
+        }
+    }
+    Ok(())
+}
+
+/// The email lookup of every document layout goes through its index, i.e.
+/// the predicate matches the indexed expression, collation included.
+/// PostgreSQL scans tables this small sequentially unless `enable_seqscan`
+/// is off.
+pub async fn email_lookups_use_indexes(db: &Db) -> anyhow::Result<()> {
+    let report = migrate::run(db).await?;
+    for (layout, index) in EMAIL_INDEXES {
+        let built = report
+            .indexes
+            .iter()
+            .any(|report| report.index == index && !matches!(report.status, IndexStatus::Skipped(_)));
+        if !layout.is_supported_by(db.backend()) || !built {
+            continue;
+        }
+
+        let store = store::for_layout(layout, db.clone())?;
+        store.clear().await?;
+        data_generator::generate_users(store.as_ref(), USERS, &WRITE).await?;
+
+        let lookup = store.fetch(UserFilter::Email("user7@example.com".to_string()));
+        let plans = explain::capture(db, ExplainMode::Plan, lookup).await?;
+        let plans = serde_json::to_string(&plans)?;
+        assert!(plans.contains(index), "{} email lookup does not use {}: {}", layout.as_str(), index, plans);
+    }
+    Ok(())
+}
+
+/// Complex documents written after simple users into the same table are
+/// the newest rows, so the complex benchmarks read them and nothing else.
+pub async fn complex_documents_round_trip(db: &Db) -> anyhow::Result<()> {
+    for compression in Compression::ALL {
+        let documents = store::documents_for(compression, db.clone(), JsonParser::default(), FetchMode::default());
+        documents.clear().await?;
+        data_generator::generate_users(documents.as_ref(), USERS, &WRITE).await?;
+        data_generator::generate_complex(documents.as_ref(), DOCUMENTS, &WRITE).await?;
+
+        let (fetched, _) = documents.fetch_documents(DOCUMENTS).await?;
+        assert_eq!(fetched.len(), DOCUMENTS as usize, "{} documents", documents.storage_type());
+        for document in fetched {
+            serde_json::from_str::<ComplexUser>(&document)?;
+        }
+
+        // Reading users skips the complex profiles and counts them
+        for fetch in FetchMode::ALL {
+            let store = store::documents_for(compression, db.clone(), JsonParser::default(), fetch);
+            let result = benchmark::read(store.as_ref(), USERS + DOCUMENTS, &RunOptions::default()).await?;
+            assert_eq!(result.records_processed, USERS as usize, "{}", result.storage_type);
+            let details = result.processing_details.expect("read details");
+            assert_eq!(details["parse_failures"], DOCUMENTS, "{}", result.storage_type);
+        }
+
+        for model in ComplexModel::ALL {
+            for fetch in FetchMode::ALL {
+                let store = store::documents_for(compression, db.clone(), JsonParser::default(), fetch);
+                let result = benchmark::complex_processing(store.as_ref(), DOCUMENTS, model, &RunOptions::default()).await?;
+                assert_eq!(result.records_processed, DOCUMENTS as usize, "{}", result.storage_type);
+                let details = result.processing_details.expect("complex processing details");
+                assert_eq!(details["parse_failures"], 0, "{}", result.storage_type);
+            }
+        }
+    }
+
+    // The SQL aggregation computes what the application computes
+    let store = JsonStore::new(db.clone());
+    let result = benchmark::complex_aggregation(&store, DOCUMENTS, &RunOptions::default()).await?;
+    let details = result.processing_details.expect("aggregation details");
+    let comparison = &details["comparison"];
+    assert_eq!(comparison["documents_compared"], DOCUMENTS);
+    assert_eq!(comparison["documents_matching"], DOCUMENTS, "{}", comparison["mismatches"]);
+    Ok(())
+}
+
+/// The complex profiles stored as rows come back complete.
+pub async fn complex_column_round_trip(db: &Db) -> anyhow::Result<()> {
+    let store = ComplexColumnStore::new(db.clone());
+    store.clear().await?;
+    data_generator::generate_complex_column(&store, DOCUMENTS, &WRITE).await?;
+    assert_eq!(store.count().await?, i64::from(DOCUMENTS));
+
+    let result = benchmark::complex_column_processing(&store, DOCUMENTS, &RunOptions::default()).await?;
+    assert_eq!(result.records_processed, DOCUMENTS as usize);
+    Ok(())
+}
@@ -0,0 +1,20 @@
+//! Round trips on the MySQL or MariaDB database in `TEST_MYSQL_URL`, skipped
+//! when it is unset. The tests clear every layout table, so point it at a
+//! scratch database.
+
+mod common;
+
+/// One test, so that the round trips never clear tables under each other.
+#[tokio::test]
+async fn round_trips() -> anyhow::Result<()> {
+    let Ok(url) = std::env::var("TEST_MYSQL_URL") else {
+        eprintln!("TEST_MYSQL_URL not set, skipping");
+        return Ok(());
+    };
+    let db = common::connect(&url).await?;
+
+    common::layouts_round_trip(&db).await?;
+    common::complex_documents_round_trip(&db).await?;
+    common::complex_column_round_trip(&db).await?;
+    common::email_lookups_use_indexes(&db).await
+}
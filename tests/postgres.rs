@@ -0,0 +1,23 @@
+//! Round trips on the PostgreSQL database in `TEST_POSTGRES_URL`, skipped
+//! when it is unset. The tests clear every layout table, so point it at a
+//! scratch database.
+
+mod common;
+
+/// One test, so that the round trips never clear tables under each other.
+#[tokio::test]
+async fn round_trips() -> anyhow::Result<()> {
+    let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+        eprintln!("TEST_POSTGRES_URL not set, skipping");
+        return Ok(());
+    };
+    let db = common::connect(&url).await?;
+
+    common::layouts_round_trip(&db).await?;
+    common::complex_documents_round_trip(&db).await?;
+    common::complex_column_round_trip(&db).await?;
+
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let db = common::connect(&format!("{}{}options[enable_seqscan]=off", url, separator)).await?;
+    common::email_lookups_use_indexes(&db).await
+}
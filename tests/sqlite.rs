@@ -0,0 +1,27 @@
+//! Round trips on a fresh in-memory SQLite database per test.
+
+mod common;
+
+async fn connect() -> anyhow::Result<structure_column_performance::db::Db> {
+    common::connect("sqlite::memory:").await
+}
+
+#[tokio::test]
+async fn layouts_round_trip() -> anyhow::Result<()> {
+    common::layouts_round_trip(&connect().await?).await
+}
+
+#[tokio::test]
+async fn complex_documents_round_trip() -> anyhow::Result<()> {
+    common::complex_documents_round_trip(&connect().await?).await
+}
+
+#[tokio::test]
+async fn complex_column_round_trip() -> anyhow::Result<()> {
+    common::complex_column_round_trip(&connect().await?).await
+}
+
+#[tokio::test]
+async fn email_lookups_use_indexes() -> anyhow::Result<()> {
+    common::email_lookups_use_indexes(&connect().await?).await
+}